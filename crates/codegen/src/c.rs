@@ -0,0 +1,213 @@
+use crate::{read_words, sources, updates};
+use module::Module;
+use std::fmt::Write;
+
+pub struct CFiles {
+    pub header: String,
+    pub source: String,
+}
+
+/// Generates a dependency-free C99 header and source pair simulating
+/// `module`. Every function is prefixed with `prefix`, and every port gets a
+/// setter or getter named after it, e.g. `adder_set_a` and `adder_get_sum`.
+/// The source expects the header to be included as `"<prefix>.h"`.
+pub fn c(module: &Module, inputs: &[String], outputs: &[String], prefix: &str) -> CFiles {
+    let size = module.connections.len();
+    // C forbids zero length arrays.
+    let words = size.div_ceil(64).max(1);
+    let guard = format!("{}_H", prefix.to_ascii_uppercase());
+    let mut header = String::new();
+    let _ = writeln!(header, "/* Generated by ryvu. Do not edit. */");
+    let _ = writeln!(header, "#ifndef {}", guard);
+    let _ = writeln!(header, "#define {}\n", guard);
+    let _ = writeln!(header, "#include <stdbool.h>");
+    let _ = writeln!(header, "#include <stdint.h>\n");
+    let _ = writeln!(
+        header,
+        "#define {}_NODES {}\n",
+        prefix.to_ascii_uppercase(),
+        size
+    );
+    let _ = writeln!(header, "struct {} {{", prefix);
+    let _ = writeln!(header, "    uint64_t charged[{}];", words);
+    let _ = writeln!(header, "    uint64_t blocked[{}];", words);
+    let _ = writeln!(header, "}};\n");
+    let _ = writeln!(header, "void {}_reset(struct {} *c);", prefix, prefix);
+    let _ = writeln!(header, "void {}_step(struct {} *c);", prefix, prefix);
+    let _ = writeln!(
+        header,
+        "void {}_charge(struct {} *c, unsigned index);",
+        prefix, prefix
+    );
+    let _ = writeln!(
+        header,
+        "bool {}_seek(const struct {} *c, unsigned index);",
+        prefix, prefix
+    );
+    for name in inputs {
+        let _ = writeln!(
+            header,
+            "void {}_set_{}(struct {} *c);",
+            prefix, name, prefix
+        );
+    }
+    for name in outputs {
+        let _ = writeln!(
+            header,
+            "bool {}_get_{}(const struct {} *c);",
+            prefix, name, prefix
+        );
+    }
+    let _ = writeln!(header, "\n#endif");
+
+    let (charging, blocking) = sources(module);
+    let mut source = String::new();
+    let _ = writeln!(source, "/* Generated by ryvu. Do not edit. */");
+    let _ = writeln!(source, "#include \"{}.h\"\n", prefix);
+    let _ = writeln!(source, "void {}_reset(struct {} *c)\n{{", prefix, prefix);
+    let _ = writeln!(source, "    unsigned i;");
+    let _ = writeln!(source, "    for (i = 0; i < {}; i++) {{", words);
+    let _ = writeln!(source, "        c->charged[i] = 0;");
+    let _ = writeln!(source, "        c->blocked[i] = 0;");
+    let _ = writeln!(source, "    }}");
+    let _ = writeln!(source, "}}\n");
+    let _ = writeln!(source, "void {}_step(struct {} *c)\n{{", prefix, prefix);
+    for word in read_words(module) {
+        let _ = writeln!(
+            source,
+            "    uint64_t a{} = c->charged[{}] & ~c->blocked[{}];",
+            word, word, word
+        );
+    }
+    updates(&mut source, "    c->charged", &charging, size.div_ceil(64));
+    updates(&mut source, "    c->blocked", &blocking, size.div_ceil(64));
+    let _ = writeln!(source, "}}\n");
+    let _ = writeln!(
+        source,
+        "void {}_charge(struct {} *c, unsigned index)\n{{",
+        prefix, prefix
+    );
+    let _ = writeln!(
+        source,
+        "    c->charged[index / 64] |= (uint64_t)1 << (index % 64);"
+    );
+    let _ = writeln!(source, "}}\n");
+    let _ = writeln!(
+        source,
+        "bool {}_seek(const struct {} *c, unsigned index)\n{{",
+        prefix, prefix
+    );
+    let _ = writeln!(
+        source,
+        "    return (c->charged[index / 64] >> (index % 64)) & 1;"
+    );
+    let _ = writeln!(source, "}}");
+    for (name, index) in inputs.iter().zip(module.inputs.iter()) {
+        let _ = writeln!(
+            source,
+            "\nvoid {}_set_{}(struct {} *c)\n{{",
+            prefix, name, prefix
+        );
+        let _ = writeln!(source, "    {}_charge(c, {});", prefix, index);
+        let _ = writeln!(source, "}}");
+    }
+    for (name, index) in outputs.iter().zip(module.outputs.iter()) {
+        let _ = writeln!(
+            source,
+            "\nbool {}_get_{}(const struct {} *c)\n{{",
+            prefix, name, prefix
+        );
+        let _ = writeln!(source, "    return {}_seek(c, {});", prefix, index);
+        let _ = writeln!(source, "}}");
+    }
+    CFiles { header, source }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::c::c;
+    use compile::compile;
+    use module::ModuleBuilder;
+    use std::{env, fs, process::Command};
+
+    #[test]
+    fn port_functions() {
+        let cr = compile("$a > x > $o", true, false);
+        let files = c(
+            &cr.module.unwrap(),
+            &cr.input_ids.unwrap(),
+            &cr.output_ids.unwrap(),
+            "relay",
+        );
+        assert!(files.header.contains("#ifndef RELAY_H"));
+        assert!(files.header.contains("void relay_set_a(struct relay *c);"));
+        assert!(files
+            .header
+            .contains("bool relay_get_o(const struct relay *c);"));
+        assert!(files.source.contains("#include \"relay.h\""));
+    }
+
+    #[test]
+    fn unread_words_compile_without_warnings() {
+        // The last word only holds the output, which no update reads.
+        let mut builder = ModuleBuilder::default();
+        for i in 0..64 {
+            builder.charge(i, i + 1);
+        }
+        builder.input(0);
+        builder.output(64);
+        let files = c(
+            &builder.build(),
+            &["a".to_owned()],
+            &["o".to_owned()],
+            "chain",
+        );
+        assert!(!files.source.contains("a1"));
+        let dir = env::temp_dir().join(format!("ryvu_codegen_c_words_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("chain.h"), files.header).unwrap();
+        fs::write(dir.join("chain.c"), files.source).unwrap();
+        let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_owned()))
+            .args(["-std=c99", "-Wall", "-Werror", "-c", "-o"])
+            .arg(dir.join("chain.o"))
+            .arg(dir.join("chain.c"))
+            .status()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn generated_code_runs() {
+        let cr = compile("$a > x > $o\n$b . x", true, false);
+        let files = c(
+            &cr.module.unwrap(),
+            &cr.input_ids.unwrap(),
+            &cr.output_ids.unwrap(),
+            "gate",
+        );
+        let main = "#include <stdio.h>\n#include \"gate.h\"\n\n\
+                    int main(void)\n{\n    struct gate g;\n    gate_reset(&g);\n    \
+                    gate_set_a(&g);\n    gate_step(&g);\n    gate_step(&g);\n    \
+                    printf(\"%d\", gate_get_o(&g));\n    gate_set_a(&g);\n    \
+                    gate_set_b(&g);\n    gate_step(&g);\n    gate_step(&g);\n    \
+                    printf(\"%d\", gate_get_o(&g));\n    return 0;\n}\n";
+        let dir = env::temp_dir().join(format!("ryvu_codegen_c_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("gate.h"), files.header).unwrap();
+        fs::write(dir.join("gate.c"), files.source).unwrap();
+        fs::write(dir.join("main.c"), main).unwrap();
+        let binary = dir.join("gate");
+        let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_owned()))
+            .args(["-std=c99", "-Wall", "-Werror", "-o"])
+            .arg(&binary)
+            .arg(dir.join("gate.c"))
+            .arg(dir.join("main.c"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let output = Command::new(&binary).output().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "10");
+    }
+}
@@ -0,0 +1,107 @@
+use compile::{compile, LexerError, ParserError};
+use module::Module;
+use std::{fmt::Write, fs, io, path::Path};
+
+pub mod c;
+pub mod rust;
+pub mod verilog;
+
+#[derive(Debug)]
+pub enum BuildError {
+    Io(io::Error),
+    Compile(Vec<LexerError>, Vec<ParserError>),
+}
+
+/// Compiles the circuit at `source_path` and returns its module together
+/// with its input and output names.
+pub fn load(source_path: &Path) -> Result<(Module, Vec<String>, Vec<String>), BuildError> {
+    let source = fs::read_to_string(source_path).map_err(BuildError::Io)?;
+    let cr = compile(&source, true, false);
+    match cr.module {
+        Some(module) => Ok((
+            module,
+            cr.input_ids.unwrap_or_default(),
+            cr.output_ids.unwrap_or_default(),
+        )),
+        None => Err(BuildError::Compile(cr.lerrors, cr.perrors)),
+    }
+}
+
+/// Generates Rust source for the circuit at `source_path` into `out_path`,
+/// telling cargo to rerun the build script when the circuit changes. Meant
+/// to be called from a build script, with the result pulled in through
+/// `include!(concat!(env!("OUT_DIR"), ...))`.
+pub fn build_rust<P, Q>(source_path: P, out_path: Q, type_name: &str) -> Result<(), BuildError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    println!("cargo:rerun-if-changed={}", source_path.as_ref().display());
+    let (module, _, _) = load(source_path.as_ref())?;
+    fs::write(out_path, rust::rust(&module, type_name)).map_err(BuildError::Io)
+}
+
+/// Sorted charging and blocking sources of every node.
+pub(crate) fn sources(module: &Module) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+    let size = module.connections.len();
+    let mut charging = vec![vec![]; size];
+    let mut blocking = vec![vec![]; size];
+    for (index, con) in module.connections.iter().enumerate() {
+        for &to in con.charging.iter() {
+            charging[to].push(index);
+        }
+        for &to in con.blocking.iter() {
+            blocking[to].push(index);
+        }
+    }
+    for list in charging.iter_mut().chain(blocking.iter_mut()) {
+        list.sort_unstable();
+    }
+    (charging, blocking)
+}
+
+/// Words of the active bitset that `updates` reads: those holding a node
+/// with an outgoing edge.
+pub(crate) fn read_words(module: &Module) -> Vec<usize> {
+    let mut words: Vec<usize> = module
+        .connections
+        .iter()
+        .enumerate()
+        .filter(|(_, con)| !con.charging.is_empty() || !con.blocking.is_empty())
+        .map(|(index, _)| index / 64)
+        .collect();
+    words.dedup();
+    words
+}
+
+/// Writes one assignment per word of `target`, setting each node's bit to
+/// the disjunction of its sources' bits in the active words `a0`, `a1`, ...
+/// The expressions are valid both in Rust over `u64` and in C over
+/// `uint64_t`.
+pub(crate) fn updates(code: &mut String, target: &str, sources: &[Vec<usize>], words: usize) {
+    for word in 0..words {
+        let mut terms = vec![];
+        for bit in 0..64 {
+            let node = word * 64 + bit;
+            if node >= sources.len() || sources[node].is_empty() {
+                continue;
+            }
+            let bits: Vec<String> = sources[node]
+                .iter()
+                .map(|&source| format!("(a{} >> {})", source / 64, source % 64))
+                .collect();
+            if bits.len() == 1 {
+                terms.push(format!("({} & 1) << {}", bits[0], bit));
+            } else {
+                terms.push(format!("(({}) & 1) << {}", bits.join(" | "), bit));
+            }
+        }
+        if terms.is_empty() {
+            let _ = writeln!(code, "{}[{}] = 0;", target, word);
+        } else {
+            let indent = " ".repeat(target.len() - target.trim_start().len() + 4);
+            let separator = format!("\n{}| ", indent);
+            let _ = writeln!(code, "{}[{}] = {};", target, word, terms.join(&separator));
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{read_words, sources, updates};
+use module::Module;
+use std::fmt::Write;
+
+/// Generates a dependency-free Rust type simulating `module`, with the same
+/// `charge`, `next` and `seek` interface as the interpreted network. Node
+/// states are packed into `u64` words and each node's update is straight-line
+/// code.
+pub fn rust(module: &Module, type_name: &str) -> String {
+    let size = module.connections.len();
+    let words = size.div_ceil(64);
+    let (charging, blocking) = sources(module);
+    let mut code = String::new();
+    let _ = writeln!(code, "/// Generated by ryvu. Do not edit.");
+    let _ = writeln!(code, "#[derive(Clone, Debug, PartialEq, Eq)]");
+    let _ = writeln!(code, "pub struct {} {{", type_name);
+    let _ = writeln!(code, "    charged: [u64; {}],", words);
+    let _ = writeln!(code, "    blocked: [u64; {}],", words);
+    let _ = writeln!(code, "}}\n");
+    let _ = writeln!(code, "#[allow(dead_code, clippy::all)]");
+    let _ = writeln!(code, "impl {} {{", type_name);
+    let _ = writeln!(code, "    pub const NODES: usize = {};", size);
+    let _ = writeln!(
+        code,
+        "    pub const INPUTS: [usize; {}] = {:?};",
+        module.inputs.len(),
+        module.inputs
+    );
+    let _ = writeln!(
+        code,
+        "    pub const OUTPUTS: [usize; {}] = {:?};\n",
+        module.outputs.len(),
+        module.outputs
+    );
+    let _ = writeln!(code, "    pub fn new() -> {} {{", type_name);
+    let _ = writeln!(code, "        {} {{", type_name);
+    let _ = writeln!(code, "            charged: [0; {}],", words);
+    let _ = writeln!(code, "            blocked: [0; {}],", words);
+    let _ = writeln!(code, "        }}");
+    let _ = writeln!(code, "    }}\n");
+    let _ = writeln!(code, "    pub fn charge(&mut self, index: usize) {{");
+    let _ = writeln!(
+        code,
+        "        self.charged[index / 64] |= 1 << (index % 64);"
+    );
+    let _ = writeln!(code, "    }}\n");
+    let _ = writeln!(code, "    pub fn seek(&self, index: usize) -> bool {{");
+    let _ = writeln!(
+        code,
+        "        self.charged[index / 64] >> (index % 64) & 1 == 1"
+    );
+    let _ = writeln!(code, "    }}\n");
+    let _ = writeln!(code, "    pub fn reset(&mut self) {{");
+    let _ = writeln!(code, "        *self = {}::new();", type_name);
+    let _ = writeln!(code, "    }}\n");
+    let _ = writeln!(code, "    pub fn next(&mut self) {{");
+    for word in read_words(module) {
+        let _ = writeln!(
+            code,
+            "        let a{} = self.charged[{}] & !self.blocked[{}];",
+            word, word, word
+        );
+    }
+    updates(&mut code, "        self.charged", &charging, words);
+    updates(&mut code, "        self.blocked", &blocking, words);
+    let _ = writeln!(code, "    }}");
+    let _ = writeln!(code, "}}\n");
+    let _ = writeln!(code, "impl Default for {} {{", type_name);
+    let _ = writeln!(code, "    fn default() -> {} {{", type_name);
+    let _ = writeln!(code, "        {}::new()", type_name);
+    let _ = writeln!(code, "    }}");
+    let _ = writeln!(code, "}}");
+    code
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rust::rust;
+    use module::ModuleBuilder;
+    use sim::network::Network;
+    use std::{env, fmt::Write, fs, process::Command};
+
+    #[test]
+    fn small_module_source() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.block(2, 1);
+        builder.input(0);
+        builder.output(1);
+        let code = rust(&builder.build(), "Gate");
+        assert!(code.contains("pub struct Gate {"));
+        assert!(code.contains("pub const INPUTS: [usize; 1] = [0];"));
+        assert!(code.contains("self.charged[0] = ((a0 >> 0) & 1) << 1;"));
+        assert!(code.contains("self.blocked[0] = ((a0 >> 2) & 1) << 1;"));
+    }
+
+    #[test]
+    fn unread_words_compile_without_warnings() {
+        // The last word only holds the output, which no update reads.
+        let mut builder = ModuleBuilder::default();
+        for i in 0..64 {
+            builder.charge(i, i + 1);
+        }
+        builder.input(0);
+        builder.output(64);
+        let code = rust(&builder.build(), "Chain");
+        assert!(!code.contains("a1"));
+        let dir = env::temp_dir().join(format!("ryvu_codegen_rust_words_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("chain.rs");
+        fs::write(&source, code).unwrap();
+        let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+        let status = Command::new(rustc)
+            .args([
+                "--edition=2018",
+                "--crate-type=lib",
+                "-D",
+                "warnings",
+                "--out-dir",
+            ])
+            .arg(&dir)
+            .arg(&source)
+            .status()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(status.success());
+    }
+
+    #[test]
+    fn generated_code_runs() {
+        // Two inputs driving a 130 node relay with taps on both sides of
+        // every word boundary, a ring in the second word blocking the third,
+        // and a second input blocking the relay across a word boundary.
+        let mut builder = ModuleBuilder::default();
+        for i in 0..129 {
+            builder.charge(i, i + 1);
+        }
+        builder.charge(70, 75);
+        builder.charge(75, 71);
+        builder.block(72, 140);
+        builder.charge(129, 140);
+        builder.charge(130, 66);
+        builder.block(130, 128);
+        builder.input(0);
+        builder.input(130);
+        for output in [62, 63, 64, 65, 66, 127, 128, 129, 140].iter() {
+            builder.output(*output);
+        }
+        let module = builder.build();
+        let trace: Vec<[bool; 2]> = (0..200)
+            .map(|tick| [tick % 7 == 0, tick % 45 == 40])
+            .collect();
+        let mut code = rust(&module, "Relay");
+        let _ = write!(
+            code,
+            "fn main() {{\n    let trace: [[bool; 2]; {}] = {:?};\n    \
+             let mut relay = Relay::new();\n    \
+             for bits in trace.iter() {{\n        \
+             for (position, &bit) in bits.iter().enumerate() {{\n            \
+             if bit {{\n                relay.charge(Relay::INPUTS[position]);\n            }}\n        }}\n        \
+             relay.next();\n        \
+             for &output in Relay::OUTPUTS.iter() {{\n            \
+             print!(\"{{}}\", relay.seek(output) as u8);\n        }}\n        \
+             println!();\n    }}\n}}\n",
+            trace.len(),
+            trace
+        );
+        let dir = env::temp_dir().join(format!("ryvu_codegen_rust_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("relay.rs");
+        let binary = dir.join("relay");
+        fs::write(&source, code).unwrap();
+        let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+        let status = Command::new(rustc)
+            .arg("--edition=2018")
+            .arg("-o")
+            .arg(&binary)
+            .arg(&source)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let output = Command::new(&binary).output().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let mut network = Network::new(module);
+        let mut expected = String::new();
+        for bits in trace.iter() {
+            network.set_inputs(bits);
+            network.next();
+            for bit in network.outputs() {
+                expected.push(if bit { '1' } else { '0' });
+            }
+            expected.push('\n');
+        }
+        // Every output sees the charge at some point.
+        for column in 0..9 {
+            assert!(expected.lines().any(|line| line.as_bytes()[column] == b'1'));
+        }
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), expected);
+    }
+}
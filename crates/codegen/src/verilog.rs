@@ -0,0 +1,575 @@
+use crate::sources;
+use module::Module;
+use std::{collections::HashSet, fmt::Write};
+
+/// Reserved words of IEEE 1364-2005, plus `logic`, which SystemVerilog
+/// tools reserve too.
+const KEYWORDS: &[&str] = &[
+    "always",
+    "and",
+    "assign",
+    "automatic",
+    "begin",
+    "buf",
+    "bufif0",
+    "bufif1",
+    "case",
+    "casex",
+    "casez",
+    "cell",
+    "cmos",
+    "config",
+    "deassign",
+    "default",
+    "defparam",
+    "design",
+    "disable",
+    "edge",
+    "else",
+    "end",
+    "endcase",
+    "endconfig",
+    "endfunction",
+    "endgenerate",
+    "endmodule",
+    "endprimitive",
+    "endspecify",
+    "endtable",
+    "endtask",
+    "event",
+    "for",
+    "force",
+    "forever",
+    "fork",
+    "function",
+    "generate",
+    "genvar",
+    "highz0",
+    "highz1",
+    "if",
+    "ifnone",
+    "incdir",
+    "include",
+    "initial",
+    "inout",
+    "input",
+    "instance",
+    "integer",
+    "join",
+    "large",
+    "liblist",
+    "library",
+    "localparam",
+    "macromodule",
+    "medium",
+    "module",
+    "nand",
+    "negedge",
+    "nmos",
+    "nor",
+    "noshowcancelled",
+    "not",
+    "notif0",
+    "notif1",
+    "or",
+    "output",
+    "parameter",
+    "pmos",
+    "posedge",
+    "primitive",
+    "pull0",
+    "pull1",
+    "pulldown",
+    "pullup",
+    "pulsestyle_ondetect",
+    "pulsestyle_onevent",
+    "rcmos",
+    "real",
+    "realtime",
+    "reg",
+    "release",
+    "repeat",
+    "rnmos",
+    "rpmos",
+    "rtran",
+    "rtranif0",
+    "rtranif1",
+    "scalared",
+    "showcancelled",
+    "signed",
+    "small",
+    "specify",
+    "specparam",
+    "strong0",
+    "strong1",
+    "supply0",
+    "supply1",
+    "table",
+    "task",
+    "time",
+    "tran",
+    "tranif0",
+    "tranif1",
+    "tri",
+    "tri0",
+    "tri1",
+    "triand",
+    "trior",
+    "trireg",
+    "unsigned",
+    "use",
+    "uwire",
+    "vectored",
+    "wait",
+    "wand",
+    "weak0",
+    "weak1",
+    "while",
+    "wire",
+    "wor",
+    "xnor",
+    "xor",
+    "logic",
+];
+
+/// Names the generated module and testbench declare themselves. Escaping
+/// does not help here, since `\clk ` and `clk` are the same identifier.
+const GENERATED: &[&str] = &[
+    "clk", "rst", "charged", "blocked", "active", "errors", "tick", "dut",
+];
+
+/// Generates a synthesizable Verilog module `name` simulating `module`, with
+/// one charged and one blocked flop per node. Every rising edge of `clk` is
+/// one tick: the input ports high on the edge charge their nodes, exactly
+/// like charging them before `Network::next`. A high `rst` discharges and
+/// unblocks every node instead.
+pub fn verilog(module: &Module, inputs: &[String], outputs: &[String], name: &str) -> String {
+    let size = module.connections.len().max(1);
+    let (charging, blocking) = sources(module);
+    let (inputs, outputs) = port_names(inputs, outputs);
+    let mut code = String::new();
+    let _ = writeln!(code, "// Generated by ryvu. Do not edit.");
+    let _ = writeln!(code, "module {} (", identifier(name));
+    let mut ports = vec!["input wire clk".to_owned(), "input wire rst".to_owned()];
+    for port in inputs.iter() {
+        ports.push(format!("input wire {}", port));
+    }
+    for port in outputs.iter() {
+        ports.push(format!("output wire {}", port));
+    }
+    let _ = writeln!(code, "    {}", ports.join(",\n    "));
+    let _ = writeln!(code, ");");
+    let _ = writeln!(code, "    reg [{}:0] charged;", size - 1);
+    let _ = writeln!(code, "    reg [{}:0] blocked;", size - 1);
+    let _ = writeln!(code, "    wire [{}:0] active;\n", size - 1);
+    for node in 0..size {
+        match module.inputs.iter().position(|&input| input == node) {
+            Some(position) => {
+                let _ = writeln!(
+                    code,
+                    "    assign active[{}] = (charged[{}] | {}) & ~blocked[{}];",
+                    node, node, inputs[position], node
+                );
+            }
+            None => {
+                let _ = writeln!(
+                    code,
+                    "    assign active[{}] = charged[{}] & ~blocked[{}];",
+                    node, node, node
+                );
+            }
+        }
+    }
+    let _ = writeln!(code, "\n    always @(posedge clk) begin");
+    let _ = writeln!(code, "        if (rst) begin");
+    let _ = writeln!(code, "            charged <= {}'b0;", size);
+    let _ = writeln!(code, "            blocked <= {}'b0;", size);
+    let _ = writeln!(code, "        end else begin");
+    for (field, sources) in [("charged", &charging), ("blocked", &blocking)] {
+        for node in 0..size {
+            let _ = writeln!(
+                code,
+                "            {}[{}] <= {};",
+                field,
+                node,
+                disjunction(sources.get(node).map(Vec::as_slice).unwrap_or(&[]))
+            );
+        }
+    }
+    let _ = writeln!(code, "        end");
+    let _ = writeln!(code, "    end\n");
+    for (port, index) in outputs.iter().zip(module.outputs.iter()) {
+        let _ = writeln!(code, "    assign {} = charged[{}];", port, index);
+    }
+    let _ = writeln!(code, "endmodule");
+    code
+}
+
+/// Generates a self-checking testbench for the module generated by
+/// `verilog`. Each vector starts from a reset, applies one line of input
+/// bits per clock cycle and compares the outputs with the matching line of
+/// `expected`. The testbench prints `PASS` or the mismatches, then finishes.
+pub fn testbench(
+    inputs: &[String],
+    outputs: &[String],
+    name: &str,
+    vectors: &[Vec<Vec<bool>>],
+    expected: &[Vec<Vec<bool>>],
+) -> String {
+    let (inputs, outputs) = port_names(inputs, outputs);
+    let mut code = String::new();
+    let _ = writeln!(code, "// Generated by ryvu. Do not edit.");
+    let _ = writeln!(code, "`timescale 1ns / 1ps\n");
+    let _ = writeln!(code, "module {};", identifier(&format!("{}_tb", name)));
+    let _ = writeln!(code, "    reg clk = 0;");
+    let _ = writeln!(code, "    reg rst = 0;");
+    for port in inputs.iter() {
+        let _ = writeln!(code, "    reg {} = 0;", port);
+    }
+    for port in outputs.iter() {
+        let _ = writeln!(code, "    wire {};", port);
+    }
+    let _ = writeln!(code, "    integer errors = 0;\n");
+    let mut connections = vec![".clk(clk)".to_owned(), ".rst(rst)".to_owned()];
+    for port in inputs.iter().chain(outputs.iter()) {
+        connections.push(format!(".{}({})", port, port));
+    }
+    let _ = writeln!(
+        code,
+        "    {} dut ({});\n",
+        identifier(name),
+        connections.join(", ")
+    );
+    let _ = writeln!(code, "    always #5 clk = ~clk;\n");
+    let _ = writeln!(code, "    task tick;");
+    let _ = writeln!(code, "        begin");
+    let _ = writeln!(code, "            @(posedge clk);");
+    let _ = writeln!(code, "            #1;");
+    let _ = writeln!(code, "        end");
+    let _ = writeln!(code, "    endtask\n");
+    let _ = writeln!(code, "    initial begin");
+    for (vector_index, (vector, expected)) in vectors.iter().zip(expected).enumerate() {
+        let _ = writeln!(code, "        // vector {}", vector_index);
+        assign(&mut code, &inputs, &vec![false; inputs.len()]);
+        let _ = writeln!(code, "        rst = 1;");
+        let _ = writeln!(code, "        tick;");
+        let _ = writeln!(code, "        rst = 0;");
+        for (tick, (input_data, output_data)) in vector.iter().zip(expected).enumerate() {
+            assign(&mut code, &inputs, input_data);
+            let _ = writeln!(code, "        tick;");
+            if outputs.is_empty() {
+                continue;
+            }
+            let bits = bits(output_data);
+            let _ = writeln!(
+                code,
+                "        if ({{{}}} !== {}) begin",
+                outputs.join(", "),
+                bits
+            );
+            let _ = writeln!(
+                code,
+                "            $display(\"vector {} tick {}: expected %b, got %b\", {}, {{{}}});",
+                vector_index,
+                tick,
+                bits,
+                outputs.join(", ")
+            );
+            let _ = writeln!(code, "            errors = errors + 1;");
+            let _ = writeln!(code, "        end");
+        }
+    }
+    let _ = writeln!(code, "        if (errors == 0)");
+    let _ = writeln!(code, "            $display(\"PASS\");");
+    let _ = writeln!(code, "        else");
+    let _ = writeln!(
+        code,
+        "            $display(\"FAIL: %0d mismatches\", errors);"
+    );
+    let _ = writeln!(code, "        $finish;");
+    let _ = writeln!(code, "    end");
+    let _ = writeln!(code, "endmodule");
+    code
+}
+
+fn assign(code: &mut String, inputs: &[String], input_data: &[bool]) {
+    if inputs.is_empty() {
+        return;
+    }
+    let assignments: Vec<String> = inputs
+        .iter()
+        .zip(input_data)
+        .map(|(port, &bit)| format!("{} = {};", port, bit as u8))
+        .collect();
+    let _ = writeln!(code, "        {}", assignments.join(" "));
+}
+
+fn bits(data: &[bool]) -> String {
+    let digits: String = data
+        .iter()
+        .map(|&bit| if bit { '1' } else { '0' })
+        .collect();
+    format!("{}'b{}", data.len(), digits)
+}
+
+fn disjunction(sources: &[usize]) -> String {
+    if sources.is_empty() {
+        return "1'b0".to_owned();
+    }
+    let terms: Vec<String> = sources
+        .iter()
+        .map(|source| format!("active[{}]", source))
+        .collect();
+    terms.join(" | ")
+}
+
+/// The Verilog identifiers of the ports. Names the generated code declares
+/// itself get a numeric suffix that no other port uses.
+fn port_names(inputs: &[String], outputs: &[String]) -> (Vec<String>, Vec<String>) {
+    let taken: HashSet<&str> = inputs.iter().chain(outputs).map(String::as_str).collect();
+    let mut renamed = HashSet::new();
+    let mut rename = |name: &String| {
+        if !GENERATED.contains(&name.as_str()) {
+            return identifier(name);
+        }
+        let mut suffix = 1;
+        loop {
+            let candidate = format!("{}_{}", name, suffix);
+            if !taken.contains(candidate.as_str()) && !renamed.contains(&candidate) {
+                renamed.insert(candidate.clone());
+                return identifier(&candidate);
+            }
+            suffix += 1;
+        }
+    };
+    let inputs = inputs.iter().map(&mut rename).collect();
+    let outputs = outputs.iter().map(&mut rename).collect();
+    (inputs, outputs)
+}
+
+/// Escapes names that would not be plain Verilog identifiers.
+fn identifier(name: &str) -> String {
+    if KEYWORDS.contains(&name) || name.starts_with(|ch: char| ch.is_ascii_digit()) {
+        format!("\\{} ", name)
+    } else {
+        name.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::verilog::{testbench, verilog};
+    use compile::compile;
+    use sim::network::Network;
+
+    /// The generated module, read back from its text. Only the statements
+    /// `verilog` emits are understood.
+    struct Interpreter {
+        inputs: Vec<String>,
+        outputs: Vec<(String, usize)>,
+        /// The input port each node is charged by, if any.
+        ports: Vec<Option<usize>>,
+        charging: Vec<Vec<usize>>,
+        blocking: Vec<Vec<usize>>,
+        charged: Vec<bool>,
+        blocked: Vec<bool>,
+    }
+
+    impl Interpreter {
+        fn new(code: &str) -> Interpreter {
+            let mut interpreter = Interpreter {
+                inputs: vec![],
+                outputs: vec![],
+                ports: vec![],
+                charging: vec![],
+                blocking: vec![],
+                charged: vec![],
+                blocked: vec![],
+            };
+            for line in code.lines().map(str::trim) {
+                if let Some(port) = line.strip_prefix("input wire ") {
+                    let port = name(port);
+                    if port != "clk" && port != "rst" {
+                        interpreter.inputs.push(port);
+                    }
+                } else if let Some(rest) = line.strip_prefix("assign active[") {
+                    let node = number(rest);
+                    let port = rest.split("| ").nth(1).map(name);
+                    let position = port
+                        .map(|port| interpreter.inputs.iter().position(|p| *p == port).unwrap());
+                    set(&mut interpreter.ports, node, position);
+                } else if let Some(rest) = line.strip_prefix("assign ") {
+                    let port = name(rest);
+                    let node = number(rest.split("charged[").nth(1).unwrap());
+                    interpreter.outputs.push((port, node));
+                } else if line.contains("] <= ") {
+                    let node = number(line.split('[').nth(1).unwrap());
+                    let sources = line
+                        .split("active[")
+                        .skip(1)
+                        .map(number)
+                        .collect::<Vec<usize>>();
+                    if line.starts_with("charged[") {
+                        set(&mut interpreter.charging, node, sources);
+                    } else {
+                        set(&mut interpreter.blocking, node, sources);
+                    }
+                }
+            }
+            let size = interpreter.ports.len();
+            interpreter.charged = vec![false; size];
+            interpreter.blocked = vec![false; size];
+            interpreter
+        }
+
+        /// One rising edge of `clk` with `rst` low.
+        fn clock(&mut self, input_data: &[bool]) -> Vec<bool> {
+            let active: Vec<bool> = (0..self.ports.len())
+                .map(|node| {
+                    let port = self.ports[node].is_some_and(|position| input_data[position]);
+                    (self.charged[node] || port) && !self.blocked[node]
+                })
+                .collect();
+            let any = |sources: &Vec<usize>| sources.iter().any(|&source| active[source]);
+            self.charged = self.charging.iter().map(any).collect();
+            self.blocked = self.blocking.iter().map(any).collect();
+            self.outputs
+                .iter()
+                .map(|&(_, node)| self.charged[node])
+                .collect()
+        }
+    }
+
+    fn set<T: Default>(values: &mut Vec<T>, index: usize, value: T) {
+        values.resize_with(values.len().max(index + 1), T::default);
+        values[index] = value;
+    }
+
+    fn number(text: &str) -> usize {
+        let digits: String = text.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse().unwrap()
+    }
+
+    /// The identifier at the start of `text`, escaped or not.
+    fn name(text: &str) -> String {
+        if text.starts_with('\\') {
+            text.split(' ').next().unwrap().to_owned()
+        } else {
+            text.chars()
+                .take_while(|&ch| ch.is_ascii_alphanumeric() || ch == '_')
+                .collect()
+        }
+    }
+
+    #[test]
+    fn gate_module() {
+        let cr = compile("$a > x > $o\n$b . x", true, false);
+        let code = verilog(
+            &cr.module.unwrap(),
+            &cr.input_ids.unwrap(),
+            &cr.output_ids.unwrap(),
+            "gate",
+        );
+        assert!(code.contains("module gate (\n    input wire clk,\n    input wire rst,\n"));
+        assert!(code.contains("    assign active[0] = (charged[0] | a) & ~blocked[0];\n"));
+        assert!(code.contains("    assign active[1] = charged[1] & ~blocked[1];\n"));
+        assert!(code.contains("            charged[1] <= active[0];\n"));
+        assert!(code.contains("            blocked[1] <= active[3];\n"));
+        assert!(code.contains("    assign o = charged[2];\n"));
+    }
+
+    #[test]
+    fn escaped_names() {
+        let cr = compile("$input > $wire", true, false);
+        let code = verilog(
+            &cr.module.unwrap(),
+            &cr.input_ids.unwrap(),
+            &cr.output_ids.unwrap(),
+            "module",
+        );
+        assert!(code.contains("module \\module  ("));
+        assert!(code.contains("    input wire \\input ,\n"));
+        assert!(code.contains("    assign \\wire  = charged[1];\n"));
+    }
+
+    #[test]
+    fn checking_testbench() {
+        let inputs = vec!["a".to_owned(), "b".to_owned()];
+        let outputs = vec!["o".to_owned()];
+        let vectors = vec![vec![vec![true, false], vec![false, false]]];
+        let expected = vec![vec![vec![false], vec![true]]];
+        let code = testbench(&inputs, &outputs, "gate", &vectors, &expected);
+        assert!(code.contains("module gate_tb;"));
+        assert!(code.contains("    gate dut (.clk(clk), .rst(rst), .a(a), .b(b), .o(o));"));
+        assert!(
+            code.contains("        a = 1; b = 0;\n        tick;\n        if ({o} !== 1'b0) begin")
+        );
+        assert!(
+            code.contains("        a = 0; b = 0;\n        tick;\n        if ({o} !== 1'b1) begin")
+        );
+        assert!(code.contains("$display(\"PASS\");"));
+    }
+
+    #[test]
+    fn generated_names_do_not_clash() {
+        let source = "$clk > q > q\n$rst > r . q\nq > $active\n$charged > $blocked\n$clk_1 > $tick";
+        let cr = compile(source, true, false);
+        let module = cr.module.unwrap();
+        let code = verilog(
+            &module,
+            &cr.input_ids.unwrap(),
+            &cr.output_ids.unwrap(),
+            "m",
+        );
+        let interpreter = Interpreter::new(&code);
+        let mut ports = interpreter.inputs.clone();
+        ports.extend(interpreter.outputs.iter().map(|(port, _)| port.clone()));
+        assert_eq!(
+            ports,
+            vec![
+                "clk_2",
+                "rst_1",
+                "charged_1",
+                "clk_1",
+                "active_1",
+                "blocked_1",
+                "tick_1"
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_network() {
+        // A latch, a ring crossing the port list and a keyword port.
+        let source = "$set > q > q, $out\n$reset > r . q\n$set > a > b > c > a\nc > $wire\n\
+                      $reset . b";
+        let cr = compile(source, true, false);
+        let module = cr.module.unwrap();
+        let code = verilog(
+            &module,
+            &cr.input_ids.unwrap(),
+            &cr.output_ids.unwrap(),
+            "m",
+        );
+        let mut interpreter = Interpreter::new(&code);
+        let mut network = Network::new(module);
+        let mut toggled = false;
+        let trace = [
+            [true, false],
+            [false, false],
+            [false, false],
+            [false, true],
+            [false, false],
+        ];
+        for _ in 0..3 {
+            for input_data in trace.iter() {
+                network.set_inputs(input_data);
+                network.next();
+                let outputs = interpreter.clock(input_data);
+                assert_eq!(outputs, network.outputs());
+                toggled |= outputs.contains(&true);
+            }
+        }
+        assert!(toggled);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::lex::SourcePosition;
+
+/// The statements of a source file, in order, as written. Statements the
+/// parser could not make sense of are left out.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Ast {
+    pub statements: Vec<Statement>,
+}
+
+/// A chain of batches joined by operators, like `a, b > c . d`. Every
+/// operator connects each identifier of the batch before it to each
+/// identifier of the batch after it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Statement {
+    pub head: Batch,
+    pub links: Vec<Link>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Link {
+    pub operator: Operator,
+    pub position: SourcePosition,
+    pub batch: Batch,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Operator {
+    Charge,
+    Block,
+}
+
+/// Identifiers separated by commas.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Batch {
+    pub idents: Vec<Ident>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Ident {
+    pub name: String,
+    /// Whether the identifier is preceded by `$`.
+    pub is_port: bool,
+    /// Position of the `$` for ports, of the name otherwise.
+    pub start: SourcePosition,
+    /// Position just past the last character of the name.
+    pub end: SourcePosition,
+}
+
+impl Statement {
+    pub fn start(&self) -> SourcePosition {
+        self.head.idents[0].start
+    }
+
+    pub fn end(&self) -> SourcePosition {
+        let last = self.links.last().map_or(&self.head, |link| &link.batch);
+        last.idents[last.idents.len() - 1].end
+    }
+
+    /// Every batch of the chain, starting with the head.
+    pub fn batches(&self) -> impl Iterator<Item = &Batch> {
+        std::iter::once(&self.head).chain(self.links.iter().map(|link| &link.batch))
+    }
+}
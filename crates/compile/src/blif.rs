@@ -0,0 +1,694 @@
+use module::{Module, ModuleBuilder, NodeConnections};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+/// A module imported from a netlist, with one name per node.
+pub struct Import {
+    pub module: Module,
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub names: Vec<String>,
+    /// Ticks between charging the inputs and the outputs reflecting them.
+    pub delay: usize,
+    /// Position of the added power input among the inputs, if the netlist
+    /// needed constants or negations.
+    pub power: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum BlifError {
+    Syntax(usize),
+    Unsupported(usize, String),
+    Undefined(String),
+    Redefined(String),
+    Cycle(String),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Signal {
+    Zero,
+    One,
+    /// A node, active on the given tick after the inputs were charged.
+    Node(usize, usize),
+}
+
+struct Cover {
+    inputs: Vec<String>,
+    output: String,
+    rows: Vec<(String, bool)>,
+    line: usize,
+}
+
+#[derive(Default)]
+struct Mapper {
+    builder: ModuleBuilder,
+    names: Vec<String>,
+    taken: HashSet<String>,
+    reserved: HashSet<String>,
+    power: Option<(usize, usize)>,
+    ground: Option<usize>,
+    delayed: HashMap<(usize, usize), usize>,
+    negated: HashMap<usize, Signal>,
+}
+
+/// Imports the first model of a BLIF netlist. Only combinational logic
+/// described by `.names` covers is supported, and it is mapped onto the
+/// following gate library, where a node is active on a tick when it is
+/// charged and not blocked:
+///
+/// - OR: every operand charges the gate node, one tick.
+/// - NOT: the power rail charges the gate node and the operand blocks it,
+///   one tick.
+/// - AND: the first operand charges the gate node and the negations of the
+///   other operands block it, two ticks. Negated literals block the node
+///   directly.
+/// - Constant one: the power rail, the power input and a node charging
+///   itself, so charging the power input on the first tick is enough.
+/// - Constant zero: a `ground` node nothing charges.
+///
+/// Inputs that feed no gate charge a sink node of their own, so that no port
+/// goes missing from `Import::source`.
+///
+/// Shorter paths are padded with buffer nodes so that every gate sees its
+/// operands on the same tick, and every output reflects the inputs charged
+/// `delay` ticks earlier.
+pub fn import_blif(text: &str) -> Result<Import, BlifError> {
+    let (inputs, outputs, covers) = read_blif(text)?;
+    let mut mapper = Mapper::default();
+    for name in inputs.iter().chain(outputs.iter()) {
+        mapper.reserved.insert(sanitize(name));
+    }
+    let mut signals = HashMap::new();
+    let mut input_names = vec![];
+    for name in inputs.iter() {
+        let node = mapper.node(sanitize(name), true);
+        mapper.builder.input(node);
+        input_names.push(mapper.names[node].clone());
+        if signals
+            .insert(name.clone(), Signal::Node(node, 0))
+            .is_some()
+        {
+            return Err(BlifError::Redefined(name.clone()));
+        }
+    }
+    for index in order(&covers, &signals)? {
+        let signal = mapper.cover(&covers[index], &signals)?;
+        signals.insert(covers[index].output.clone(), signal);
+    }
+    let mut output_signals = vec![];
+    for name in outputs.iter() {
+        match signals.get(name) {
+            Some(&signal) => output_signals.push(signal),
+            None => return Err(BlifError::Undefined(name.clone())),
+        }
+    }
+    let depth = output_signals
+        .iter()
+        .map(|signal| match signal {
+            Signal::Node(_, time) => *time,
+            _ => 0,
+        })
+        .max()
+        .unwrap_or(0);
+    let mut output_names = vec![];
+    for (name, signal) in outputs.iter().zip(output_signals) {
+        let node = mapper.node(sanitize(name), true);
+        output_names.push(mapper.names[node].clone());
+        if signal == Signal::Zero {
+            let ground = mapper.ground();
+            mapper.builder.charge(ground, node);
+        } else {
+            mapper.connect(signal, node, depth, true);
+        }
+        mapper.builder.output(node);
+    }
+    let power = mapper.power.map(|_| input_names.len());
+    if let Some((node, _)) = mapper.power {
+        input_names.push(mapper.names[node].clone());
+    }
+    let mut module = mapper.builder.build();
+    mapper.sink_inputs(&mut module);
+    Ok(Import {
+        module,
+        inputs: input_names,
+        outputs: output_names,
+        names: mapper.names,
+        delay: depth + 1,
+        power,
+    })
+}
+
+impl Import {
+    /// Renders the module as ryvu source. Ports are numbered by first
+    /// appearance, so the statements of each input come first, in order,
+    /// then the edges into each output, in order, then everything else.
+    /// Compiling the source gives back the module with its ports in place.
+    pub fn source(&self) -> String {
+        let module = &self.module;
+        let mut edges = vec![];
+        for (from, con) in module.connections.iter().enumerate() {
+            for (targets, is_charge) in [(&con.charging, true), (&con.blocking, false)] {
+                for &to in targets.iter() {
+                    let key = if let Some(output) = position(&module.outputs, to) {
+                        (1, output)
+                    } else if let Some(input) = position(&module.inputs, from) {
+                        (0, input)
+                    } else {
+                        (2, 0)
+                    };
+                    edges.push((key, from, !is_charge, to));
+                }
+            }
+        }
+        edges.sort_unstable();
+        let mut text = String::new();
+        let mut start = 0;
+        while start < edges.len() {
+            let (key, from, is_block, _) = edges[start];
+            let end = edges[start..]
+                .iter()
+                .position(|&(k, f, b, _)| (k, f, b) != (key, from, is_block))
+                .map_or(edges.len(), |length| start + length);
+            let targets: Vec<String> = edges[start..end]
+                .iter()
+                .map(|&(_, _, _, to)| self.ident(to))
+                .collect();
+            let operator = if is_block { '.' } else { '>' };
+            let _ = writeln!(
+                text,
+                "{} {} {}",
+                self.ident(from),
+                operator,
+                targets.join(", ")
+            );
+            start = end;
+        }
+        text
+    }
+
+    fn ident(&self, index: usize) -> String {
+        if self.module.inputs.contains(&index) || self.module.outputs.contains(&index) {
+            format!("${}", self.names[index])
+        } else {
+            self.names[index].clone()
+        }
+    }
+}
+
+impl Mapper {
+    /// Adds a node with a unique name. Port names are kept for the port
+    /// nodes, which are added with `port` set.
+    fn node(&mut self, name: String, port: bool) -> usize {
+        let mut unique = name.clone();
+        if !(port && self.reserved.remove(&name)) {
+            let mut suffix = 2;
+            while self.taken.contains(&unique) || self.reserved.contains(&unique) {
+                unique = format!("{}_{}", name, suffix);
+                suffix += 1;
+            }
+        }
+        self.taken.insert(unique.clone());
+        self.names.push(unique);
+        self.names.len() - 1
+    }
+
+    /// A node nothing charges, driving the outputs that are constant zero so
+    /// that every output appears in the rendered source.
+    fn ground(&mut self) -> usize {
+        if self.ground.is_none() {
+            self.ground = Some(self.node("ground".to_owned(), false));
+        }
+        self.ground.unwrap()
+    }
+
+    /// Gives every input that reaches no internal node a sink node to charge,
+    /// so that the rendered source still mentions it ahead of the outputs.
+    fn sink_inputs(&mut self, module: &mut Module) {
+        for &input in module.inputs.iter() {
+            let con = &module.connections[input];
+            let internal = con
+                .charging
+                .iter()
+                .chain(con.blocking.iter())
+                .any(|to| !module.outputs.contains(to));
+            if !internal {
+                let sink = self.node(format!("{}_sink", self.names[input]), false);
+                module.connections[input].charging.insert(sink);
+            }
+        }
+        module
+            .connections
+            .resize_with(self.names.len(), NodeConnections::default);
+    }
+
+    fn power(&mut self) -> (usize, usize) {
+        if self.power.is_none() {
+            let input = self.node("power".to_owned(), false);
+            let hold = self.node("power_hold".to_owned(), false);
+            self.builder.input(input);
+            self.builder.charge(input, hold);
+            self.builder.charge(hold, hold);
+            self.power = Some((input, hold));
+        }
+        self.power.unwrap()
+    }
+
+    fn cover(
+        &mut self,
+        cover: &Cover,
+        signals: &HashMap<String, Signal>,
+    ) -> Result<Signal, BlifError> {
+        if cover.rows.is_empty() {
+            return Ok(Signal::Zero);
+        }
+        let on_set = cover.rows[0].1;
+        if cover.rows.iter().any(|row| row.1 != on_set) {
+            return Err(BlifError::Syntax(cover.line));
+        }
+        let operands: Vec<Signal> = cover.inputs.iter().map(|name| signals[name]).collect();
+        let name = sanitize(&cover.output);
+        let single = cover.rows.len() == 1 && on_set;
+        let mut cubes = vec![];
+        for (index, (cube, _)) in cover.rows.iter().enumerate() {
+            let mut positive = vec![];
+            let mut negative = vec![];
+            for (literal, &operand) in cube.chars().zip(operands.iter()) {
+                match literal {
+                    '1' => positive.push(operand),
+                    '0' => negative.push(operand),
+                    _ => {}
+                }
+            }
+            let cube_name = if single {
+                name.clone()
+            } else {
+                format!("{}_c{}", name, index)
+            };
+            cubes.push(self.cube(positive, negative, cube_name));
+        }
+        Ok(if single {
+            cubes[0]
+        } else if on_set {
+            self.gate(cubes, vec![], name)
+        } else {
+            self.gate(vec![Signal::One], cubes, name)
+        })
+    }
+
+    fn cube(&mut self, positive: Vec<Signal>, mut negative: Vec<Signal>, name: String) -> Signal {
+        if positive.is_empty() {
+            return self.gate(vec![Signal::One], negative, name);
+        }
+        if positive.len() == 1 && negative.is_empty() {
+            return positive[0];
+        }
+        for &operand in positive[1..].iter() {
+            let negation = self.negate(operand);
+            negative.push(negation);
+        }
+        self.gate(vec![positive[0]], negative, name)
+    }
+
+    fn negate(&mut self, signal: Signal) -> Signal {
+        match signal {
+            Signal::Zero => Signal::One,
+            Signal::One => Signal::Zero,
+            Signal::Node(node, _) => {
+                if let Some(&negation) = self.negated.get(&node) {
+                    return negation;
+                }
+                let name = format!("{}_n", self.names[node]);
+                let negation = self.gate(vec![Signal::One], vec![signal], name);
+                self.negated.insert(node, negation);
+                negation
+            }
+        }
+    }
+
+    /// A node active when any of `chargers` and none of `blockers` is.
+    fn gate(&mut self, chargers: Vec<Signal>, blockers: Vec<Signal>, name: String) -> Signal {
+        let mut chargers: Vec<Signal> = chargers
+            .into_iter()
+            .filter(|&s| s != Signal::Zero)
+            .collect();
+        let blockers: Vec<Signal> = blockers
+            .into_iter()
+            .filter(|&s| s != Signal::Zero)
+            .collect();
+        if chargers.is_empty() || blockers.contains(&Signal::One) {
+            return Signal::Zero;
+        }
+        if chargers.contains(&Signal::One) {
+            if blockers.is_empty() {
+                return Signal::One;
+            }
+            chargers = vec![Signal::One];
+        }
+        let time = chargers
+            .iter()
+            .chain(blockers.iter())
+            .map(|signal| match signal {
+                Signal::Node(_, time) => *time,
+                _ => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        let node = self.node(name, false);
+        for signal in chargers {
+            self.connect(signal, node, time, true);
+        }
+        for signal in blockers {
+            self.connect(signal, node, time, false);
+        }
+        Signal::Node(node, time + 1)
+    }
+
+    /// Connects `signal`, delayed until `time`, to `to`.
+    fn connect(&mut self, signal: Signal, to: usize, time: usize, is_charge: bool) {
+        match signal {
+            Signal::Zero => {}
+            Signal::One => {
+                let (input, hold) = self.power();
+                self.builder.connect(input, to, is_charge);
+                self.builder.connect(hold, to, is_charge);
+            }
+            Signal::Node(node, start) => {
+                let from = self.delay(node, start, time);
+                self.builder.connect(from, to, is_charge);
+            }
+        }
+    }
+
+    fn delay(&mut self, node: usize, start: usize, time: usize) -> usize {
+        let mut current = node;
+        for tick in start + 1..=time {
+            current = match self.delayed.get(&(node, tick)) {
+                Some(&buffer) => buffer,
+                None => {
+                    let buffer = self.node(format!("{}_d{}", self.names[node], tick), false);
+                    self.builder.charge(current, buffer);
+                    self.delayed.insert((node, tick), buffer);
+                    buffer
+                }
+            };
+        }
+        current
+    }
+}
+
+/// Orders the covers so that every cover comes after the covers it reads.
+fn order(covers: &[Cover], signals: &HashMap<String, Signal>) -> Result<Vec<usize>, BlifError> {
+    let mut definitions = HashMap::new();
+    for (index, cover) in covers.iter().enumerate() {
+        if signals.contains_key(&cover.output)
+            || definitions.insert(cover.output.as_str(), index).is_some()
+        {
+            return Err(BlifError::Redefined(cover.output.clone()));
+        }
+    }
+    let mut pending = vec![0; covers.len()];
+    let mut readers = vec![vec![]; covers.len()];
+    for (index, cover) in covers.iter().enumerate() {
+        for input in cover.inputs.iter() {
+            match definitions.get(input.as_str()) {
+                Some(&definition) => {
+                    pending[index] += 1;
+                    readers[definition].push(index);
+                }
+                None if signals.contains_key(input) => {}
+                None => return Err(BlifError::Undefined(input.clone())),
+            }
+        }
+    }
+    let mut queue: VecDeque<usize> = (0..covers.len()).filter(|&i| pending[i] == 0).collect();
+    let mut order = vec![];
+    while let Some(index) = queue.pop_front() {
+        order.push(index);
+        for &reader in readers[index].iter() {
+            pending[reader] -= 1;
+            if pending[reader] == 0 {
+                queue.push_back(reader);
+            }
+        }
+    }
+    match (0..covers.len()).find(|&i| pending[i] != 0) {
+        Some(index) => Err(BlifError::Cycle(covers[index].output.clone())),
+        None => Ok(order),
+    }
+}
+
+type Netlist = (Vec<String>, Vec<String>, Vec<Cover>);
+
+fn read_blif(text: &str) -> Result<Netlist, BlifError> {
+    let mut inputs = vec![];
+    let mut outputs = vec![];
+    let mut covers: Vec<Cover> = vec![];
+    let mut in_cover = false;
+    for (line, words) in logical_lines(text) {
+        let first = words[0].as_str();
+        if !first.starts_with('.') {
+            let cover = match covers.last_mut() {
+                Some(cover) if in_cover => cover,
+                _ => return Err(BlifError::Syntax(line)),
+            };
+            let row = match (cover.inputs.len(), words.len()) {
+                (0, 1) => (String::new(), words[0].as_str()),
+                (_, 2) => (words[0].clone(), words[1].as_str()),
+                _ => return Err(BlifError::Syntax(line)),
+            };
+            let valid = row.0.len() == cover.inputs.len()
+                && row.0.chars().all(|ch| ch == '0' || ch == '1' || ch == '-')
+                && (row.1 == "0" || row.1 == "1");
+            if !valid {
+                return Err(BlifError::Syntax(line));
+            }
+            cover.rows.push((row.0, row.1 == "1"));
+            continue;
+        }
+        in_cover = false;
+        match first {
+            ".model" => {}
+            ".inputs" => inputs.extend(words[1..].iter().cloned()),
+            ".outputs" => outputs.extend(words[1..].iter().cloned()),
+            ".names" => {
+                if words.len() < 2 {
+                    return Err(BlifError::Syntax(line));
+                }
+                covers.push(Cover {
+                    inputs: words[1..words.len() - 1].to_vec(),
+                    output: words[words.len() - 1].clone(),
+                    rows: vec![],
+                    line,
+                });
+                in_cover = true;
+            }
+            ".end" => break,
+            _ => return Err(BlifError::Unsupported(line, first.to_owned())),
+        }
+    }
+    Ok((inputs, outputs, covers))
+}
+
+/// Splits the text into non-empty lines of words, joining continued lines
+/// and dropping comments. Lines are numbered from zero.
+fn logical_lines(text: &str) -> Vec<(usize, Vec<String>)> {
+    let mut lines = vec![];
+    let mut current: Option<(usize, Vec<String>)> = None;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim_end();
+        let (line, continued) = match line.strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let words = line.split_whitespace().map(str::to_owned);
+        current.get_or_insert((number, vec![])).1.extend(words);
+        if !continued {
+            let (start, words) = current.take().unwrap();
+            if !words.is_empty() {
+                lines.push((start, words));
+            }
+        }
+    }
+    if let Some((start, words)) = current {
+        if !words.is_empty() {
+            lines.push((start, words));
+        }
+    }
+    lines
+}
+
+fn position(nodes: &[usize], node: usize) -> Option<usize> {
+    nodes.iter().position(|&other| other == node)
+}
+
+/// Turns a netlist signal name into a ryvu identifier. Names that would lex
+/// as something else, like `1` or `mod`, get a prefix or suffix.
+fn sanitize(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|ch| {
+            if ch.is_ascii_alphanumeric() || ch == '_' {
+                ch
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "n".to_owned()
+    } else if name.starts_with(|ch: char| ch.is_ascii_digit()) {
+        format!("n{}", name)
+    } else if name == "mod" {
+        "mod_".to_owned()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        blif::{import_blif, BlifError, Import},
+        compile,
+    };
+    use module::Module;
+
+    /// Charges the inputs set in `input` on the first tick, along with the
+    /// power input, and returns the outputs once the delay has passed.
+    fn run(import: &Import, input: &[bool]) -> Vec<bool> {
+        simulate(&import.module, import.power, import.delay, input)
+    }
+
+    fn simulate(module: &Module, power: Option<usize>, delay: usize, input: &[bool]) -> Vec<bool> {
+        let size = module.connections.len();
+        let mut charged = vec![false; size];
+        let mut blocked = vec![false; size];
+        for (position, &bit) in input.iter().enumerate() {
+            charged[module.inputs[position]] |= bit;
+        }
+        if let Some(power) = power {
+            charged[module.inputs[power]] = true;
+        }
+        for _ in 0..delay {
+            let mut next_charged = vec![false; size];
+            let mut next_blocked = vec![false; size];
+            for index in 0..size {
+                if charged[index] && !blocked[index] {
+                    for &to in module.connections[index].charging.iter() {
+                        next_charged[to] = true;
+                    }
+                    for &to in module.connections[index].blocking.iter() {
+                        next_blocked[to] = true;
+                    }
+                }
+            }
+            charged = next_charged;
+            blocked = next_blocked;
+        }
+        module.outputs.iter().map(|&index| charged[index]).collect()
+    }
+
+    /// Compiles the rendered source and checks it behaves like the import on
+    /// every input combination.
+    fn round_trip(import: &Import) {
+        let cr = compile(&import.source(), true, false);
+        assert!(cr.success, "{:?} {:?}", cr.lerrors, cr.perrors);
+        let module = cr.module.unwrap();
+        assert_eq!(cr.input_ids.unwrap(), import.inputs);
+        assert_eq!(cr.output_ids.unwrap(), import.outputs);
+        assert_eq!(module.connections.len(), import.module.connections.len());
+        let inputs = import.inputs.len() - import.power.map_or(0, |_| 1);
+        for bits in 0..1 << inputs {
+            let input: Vec<bool> = (0..inputs).map(|bit| bits & 1 << bit != 0).collect();
+            assert_eq!(
+                simulate(&module, import.power, import.delay, &input),
+                run(import, &input)
+            );
+        }
+    }
+
+    #[test]
+    fn gate_library() {
+        let text = ".model gates\n\
+                    .inputs a b c\n\
+                    .outputs and or not mux zero one\n\
+                    .names a b and\n11 1\n\
+                    .names a b or\n1- 1\n-1 1\n\
+                    .names a not\n0 1\n\
+                    .names a b c mux\n11- 1\n0-1 1\n\
+                    .names zero\n\
+                    .names one\n1\n\
+                    .end\n";
+        let import = import_blif(text).unwrap();
+        assert_eq!(import.inputs, vec!["a", "b", "c", "power"]);
+        assert_eq!(import.power, Some(3));
+        for bits in 0..8 {
+            let (a, b, c) = (bits & 1 != 0, bits & 2 != 0, bits & 4 != 0);
+            let mux = if a { b } else { c };
+            assert_eq!(
+                run(&import, &[a, b, c]),
+                vec![a && b, a || b, !a, mux, false, true]
+            );
+        }
+        round_trip(&import);
+    }
+
+    #[test]
+    fn off_set_and_continuation() {
+        let text = ".inputs a \\\n b\n.outputs y\n.names a b y # nand\n11 0\n";
+        let import = import_blif(text).unwrap();
+        for bits in 0..4 {
+            let (a, b) = (bits & 1 != 0, bits & 2 != 0);
+            assert_eq!(run(&import, &[a, b]), vec![!(a && b)]);
+        }
+    }
+
+    #[test]
+    fn source_round_trip() {
+        let text = ".inputs a b\n.outputs y\n.names a b t\n11 1\n.names t y\n0 1\n";
+        round_trip(&import_blif(text).unwrap());
+        // Outputs fed in reverse order, an unused input, a constant output
+        // and an output wired straight to an input.
+        let text = ".inputs a b c\n.outputs y z k w\n.names b z\n0 1\n\
+                    .names a y\n1 1\n.names k\n.names b w\n1 1\n";
+        let import = import_blif(text).unwrap();
+        round_trip(&import);
+        assert!(import.names.contains(&"c_sink".to_owned()));
+    }
+
+    #[test]
+    fn names_are_identifiers() {
+        let text = ".inputs 1 3.2 a.b a_b mod\n.outputs 1_d1 n1\n\
+                    .names 1 3.2 1_d1\n11 1\n.names a.b a_b mod n1\n1-- 1\n-1- 1\n--1 1\n";
+        let import = import_blif(text).unwrap();
+        assert_eq!(
+            import.inputs,
+            vec!["n1", "n3_2", "a_b", "a_b_2", "mod_", "power"]
+        );
+        assert_eq!(import.outputs, vec!["n1_d1", "n1_3"]);
+        let mut names = import.names.clone();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), import.names.len());
+        round_trip(&import);
+    }
+
+    #[test]
+    fn netlist_errors() {
+        let undefined = ".inputs a\n.outputs y\n.names a b y\n11 1\n";
+        assert_eq!(
+            import_blif(undefined).err(),
+            Some(BlifError::Undefined("b".to_owned()))
+        );
+        let cycle = ".inputs a\n.outputs y\n.names a z y\n11 1\n.names y z\n1 1\n";
+        assert_eq!(
+            import_blif(cycle).err(),
+            Some(BlifError::Cycle("y".to_owned()))
+        );
+        let latch = ".inputs a\n.outputs y\n.latch a y 0\n";
+        assert_eq!(
+            import_blif(latch).err(),
+            Some(BlifError::Unsupported(2, ".latch".to_owned()))
+        );
+    }
+}
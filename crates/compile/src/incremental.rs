@@ -0,0 +1,178 @@
+use crate::{
+    ast::{Ast, Batch, Ident, Statement},
+    compile, finish,
+    lex::{lex, SourcePosition},
+    lower::lower,
+    parse::parse_ast,
+    CompilationResult,
+};
+use std::collections::HashMap;
+
+/// Compiles successive versions of a source, lexing and parsing only the
+/// statements whose text changed since the last call.
+///
+/// The source is cut into groups of whole lines at statement boundaries, so a
+/// statement continued on the next line by a leading or trailing operator
+/// stays in one group. Groups are cached by their text, which means moving or
+/// duplicating statements costs nothing. Lowering and translation depend on
+/// every statement and always run over the whole source. If any group has
+/// errors, the whole source is compiled with `compile` so the diagnostics are
+/// exactly the same.
+pub struct IncrementalCompiler {
+    gen_ids: bool,
+    io_min: bool,
+    cache: HashMap<String, Option<Vec<Statement>>>,
+    reused: usize,
+}
+
+impl IncrementalCompiler {
+    /// Takes the same flags as `compile`.
+    pub fn new(gen_ids: bool, io_min: bool) -> IncrementalCompiler {
+        IncrementalCompiler {
+            gen_ids,
+            io_min,
+            cache: HashMap::new(),
+            reused: 0,
+        }
+    }
+
+    pub fn compile(&mut self, source: &str) -> CompilationResult {
+        let mut cache = HashMap::new();
+        let mut statements = vec![];
+        let mut clean = true;
+        self.reused = 0;
+        for (offset, text) in groups(source) {
+            let parsed = match self.cache.remove(&text) {
+                Some(parsed) => {
+                    self.reused += 1;
+                    parsed
+                }
+                None => parse_group(&text),
+            };
+            match &parsed {
+                Some(group) => statements.extend(group.iter().map(|s| shift_statement(s, offset))),
+                None => clean = false,
+            }
+            cache.insert(text, parsed);
+        }
+        self.cache = cache;
+        if !clean {
+            return compile(source, self.gen_ids, self.io_min);
+        }
+        let (connections, perrors) = lower(&Ast { statements }, self.io_min);
+        finish(connections, vec![], perrors, self.gen_ids)
+    }
+
+    /// How many line groups the last `compile` took from the cache.
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+}
+
+/// Lexes and parses one group, or `None` if either stage reported errors.
+fn parse_group(text: &str) -> Option<Vec<Statement>> {
+    let (tokens, lerrors) = lex(text);
+    if !lerrors.is_empty() {
+        return None;
+    }
+    let (ast, perrors) = parse_ast(tokens);
+    if !perrors.is_empty() {
+        return None;
+    }
+    Some(ast.statements)
+}
+
+/// Splits `source` into groups of lines, each with the number of its first
+/// line. A line joins the group before it when it starts with an operator or
+/// a comma, when the group ends with one, or when it holds no statement.
+fn groups(source: &str) -> Vec<(usize, String)> {
+    let mut groups: Vec<(usize, String)> = vec![];
+    let mut open = true;
+    for (number, line) in source.split('\n').enumerate() {
+        let code = line.split('#').next().unwrap_or("").trim_matches(' ');
+        let joins = open || code.is_empty() || code.starts_with(['>', '.', ',']);
+        match groups.last_mut() {
+            Some((_, text)) if joins => {
+                text.push('\n');
+                text.push_str(line);
+            }
+            _ => groups.push((number, line.to_owned())),
+        }
+        if !code.is_empty() {
+            open = code.ends_with(['>', '.', ',', '$']);
+        }
+    }
+    groups
+}
+
+fn shift_statement(statement: &Statement, lines: usize) -> Statement {
+    let mut statement = statement.clone();
+    shift_batch(&mut statement.head, lines);
+    for link in statement.links.iter_mut() {
+        link.position = shift(link.position, lines);
+        shift_batch(&mut link.batch, lines);
+    }
+    statement
+}
+
+fn shift_batch(batch: &mut Batch, lines: usize) {
+    for Ident { start, end, .. } in batch.idents.iter_mut() {
+        *start = shift(*start, lines);
+        *end = shift(*end, lines);
+    }
+}
+
+fn shift(position: SourcePosition, lines: usize) -> SourcePosition {
+    SourcePosition::new(position.line() + lines, position.ch())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{compile, incremental::IncrementalCompiler};
+
+    fn assert_same(compiler: &mut IncrementalCompiler, source: &str) {
+        let expected = compile(source, true, false);
+        let result = compiler.compile(source);
+        assert_eq!(result.success, expected.success);
+        assert_eq!(result.module, expected.module);
+        assert_eq!(result.node_ids, expected.node_ids);
+        assert_eq!(result.input_ids, expected.input_ids);
+        assert_eq!(result.output_ids, expected.output_ids);
+        assert_eq!(result.lerrors, expected.lerrors);
+        assert_eq!(result.perrors, expected.perrors);
+        let (map, expected_map) = (result.source_map, expected.source_map);
+        assert_eq!(map.is_some(), expected_map.is_some());
+        if let (Some(map), Some(expected_map)) = (map, expected_map) {
+            assert_eq!(map.nodes, expected_map.nodes);
+            assert_eq!(map.edges, expected_map.edges);
+        }
+    }
+
+    #[test]
+    fn matches_full_compile() {
+        let mut compiler = IncrementalCompiler::new(true, false);
+        let sources = [
+            "$a > b\nb . c > $o",
+            "$a > b\n# note\nb . c > $o\nc > d; d > c",
+            "$a > b\n\n> c . d\nb,\nc > $o",
+            "$a > b\nb . c > $o\n1x > y",
+            "$a > b\nb >\n",
+            "$a > b\n$b > c",
+            "",
+        ];
+        for source in sources.iter() {
+            assert_same(&mut compiler, source);
+        }
+    }
+
+    #[test]
+    fn reuses_unchanged_statements() {
+        let mut compiler = IncrementalCompiler::new(true, false);
+        assert!(compiler.compile("$a > b\nb > c\nc > $o").success);
+        assert_eq!(compiler.reused(), 0);
+        assert_same(&mut compiler, "$a > b\nb . c\nc > $o");
+        assert_eq!(compiler.reused(), 2);
+        assert_same(&mut compiler, "x > b\n$a > b\nb . c\nc > $o");
+        assert_eq!(compiler.reused(), 3);
+    }
+}
@@ -8,21 +8,25 @@ struct Lexer {
     buffer_state: BufferState,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Default)]
 enum BufferState {
     Space,
     Ident,
     InvIdent,
+    #[default]
     Empty,
     Comment,
 }
 
+/// A character the lexer could not make sense of.
 #[derive(PartialEq, Eq, Debug)]
 pub struct LexerError {
     error_kind: LexerErrorKind,
     position: SourcePosition,
 }
 
+/// A piece of source text. Every character of the source, spaces and
+/// comments included, belongs to exactly one token.
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Token {
     kind: TokenKind,
@@ -52,6 +56,7 @@ pub enum TokenKind {
     Rcrb,
 }
 
+/// A line and a character within it, both counted from zero.
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub struct SourcePosition {
     line: usize,
@@ -75,6 +80,7 @@ macro_rules! token {
     };
 }
 
+/// Splits `source` into tokens, the first stage of `compile`.
 pub fn lex(source: &str) -> (Vec<Token>, Vec<LexerError>) {
     let mut lexer = Lexer::default();
     lexer.lex(source)
@@ -166,8 +172,8 @@ impl Lexer {
         self.push_ident();
         self.push_comment();
         (
-            std::mem::replace(&mut self.tokens, vec![]),
-            std::mem::replace(&mut self.errors, vec![]),
+            std::mem::take(&mut self.tokens),
+            std::mem::take(&mut self.errors),
         )
     }
     fn is_alphanumeric(ch: char) -> bool {
@@ -205,10 +211,10 @@ impl Lexer {
         self.clear_buffer();
     }
     fn is_alphabetic(ch: char) -> bool {
-        (ch >= 'a' && ch <= 'z') || (ch >= 'A' && ch <= 'Z') || ch == '_'
+        ch.is_ascii_alphabetic() || ch == '_'
     }
     fn is_numeric(ch: char) -> bool {
-        ch >= '0' && ch <= '9'
+        ch.is_ascii_digit()
     }
     fn push_buffer(&mut self, mut kind: TokenKind) {
         if kind == TokenKind::Identifier && &self.buffer == "mod" {
@@ -225,12 +231,6 @@ impl Lexer {
     }
 }
 
-impl Default for BufferState {
-    fn default() -> Self {
-        BufferState::Empty
-    }
-}
-
 impl Token {
     pub fn new(kind: TokenKind, text: String, position: SourcePosition) -> Token {
         Token {
@@ -258,6 +258,24 @@ impl SourcePosition {
     pub fn new(line: usize, ch: usize) -> SourcePosition {
         SourcePosition { line, ch }
     }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn ch(&self) -> usize {
+        self.ch
+    }
+}
+
+impl LexerError {
+    pub fn kind(&self) -> &LexerErrorKind {
+        &self.error_kind
+    }
+
+    pub fn position(&self) -> SourcePosition {
+        self.position
+    }
 }
 
 #[cfg(test)]
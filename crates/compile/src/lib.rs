@@ -1,15 +1,21 @@
-use lex::lex;
-pub use lex::LexerError;
+pub use ast::Ast;
+pub use incremental::IncrementalCompiler;
+pub use lex::{lex, LexerError, LexerErrorKind, SourcePosition, Token, TokenKind};
+pub use lower::lower;
 use module::Module;
-use parse::parse;
-pub use parse::ParserError;
-use translate::translate;
+pub use parse::{parse, parse_ast, ParserError};
+pub use translate::{translate, Connection, IdentKind, Identifier, SourceMap, TranslationResult};
 
+pub mod ast;
 #[macro_use]
-mod lex;
+pub mod lex;
 #[macro_use]
-mod translate;
-mod parse;
+pub mod translate;
+pub mod blif;
+pub mod incremental;
+pub mod lower;
+pub mod parse;
+pub mod rename;
 
 pub struct CompilationResult {
     pub module: Option<Module>,
@@ -18,12 +24,28 @@ pub struct CompilationResult {
     pub lerrors: Vec<LexerError>,
     pub input_ids: Option<Vec<String>>,
     pub output_ids: Option<Vec<String>>,
+    pub node_ids: Option<Vec<String>>,
+    pub source_map: Option<SourceMap>,
 }
 
+/// Runs the whole pipeline: `lex`, `parse` (itself `parse_ast` then
+/// `lower`), then `translate` if neither stage reported errors. Each stage is
+/// public, so tools can stop after any of them or feed their own input to the
+/// next.
 pub fn compile(source: &str, gen_ids: bool, io_min: bool) -> CompilationResult {
     let (tokens, lexer_error) = lex(source);
     let (connections, parser_error) = parse(tokens, io_min);
-    if lexer_error.len() > 0 || parser_error.len() > 0 {
+    finish(connections, lexer_error, parser_error, gen_ids)
+}
+
+/// Translates `connections` unless an earlier stage reported errors.
+fn finish(
+    connections: Vec<Connection>,
+    lexer_error: Vec<LexerError>,
+    parser_error: Vec<ParserError>,
+    gen_ids: bool,
+) -> CompilationResult {
+    if !lexer_error.is_empty() || !parser_error.is_empty() {
         CompilationResult {
             module: None,
             success: false,
@@ -31,6 +53,8 @@ pub fn compile(source: &str, gen_ids: bool, io_min: bool) -> CompilationResult {
             lerrors: lexer_error,
             input_ids: None,
             output_ids: None,
+            node_ids: None,
+            source_map: None,
         }
     } else {
         let tr = translate(connections, gen_ids);
@@ -45,6 +69,8 @@ pub fn compile(source: &str, gen_ids: bool, io_min: bool) -> CompilationResult {
             lerrors: vec![],
             input_ids,
             output_ids,
+            node_ids: tr.names,
+            source_map: tr.source_map,
         }
     }
 }
@@ -54,7 +80,7 @@ mod test {
 
     use module::ModuleBuilder;
 
-    use crate::{compile, Module};
+    use crate::{compile, lex, parse, translate, IdentKind, Module, SourcePosition, TokenKind};
 
     fn compile_case(source: &str, module: Module) {
         let cr = compile(source, false, false);
@@ -135,10 +161,52 @@ mod test {
         compile_case("\n\n\n\na . b", builder.build());
     }
 
+    #[test]
+    fn source_map_lines() {
+        let cr = compile("a > b\n\nb . c,\nd", true, false);
+        let source_map = cr.source_map.unwrap();
+        assert_eq!(source_map.nodes, vec![0, 0, 2, 3]);
+        assert_eq!(source_map.edges[&(1, 2, false)], 2);
+        assert_eq!(source_map.edges[&(1, 3, false)], 3);
+    }
+
+    #[test]
+    fn source_map_multiline_statement() {
+        let cr = compile(
+            "a >
+ b .
+
+ c",
+            true,
+            false,
+        );
+        let source_map = cr.source_map.unwrap();
+        assert_eq!(source_map.nodes, vec![0, 1, 3]);
+        assert_eq!(source_map.edges[&(0, 1, true)], 1);
+        assert_eq!(source_map.edges[&(1, 2, false)], 3);
+    }
+
     #[test]
     fn multiple_endline_semicolon() {
         let mut builder = ModuleBuilder::default();
         builder.block(0, 1);
         compile_case("\n\n  \n;;\na  . b;\n\n;;; \n;\n", builder.build());
     }
+
+    #[test]
+    fn pipeline_stages() {
+        let source = "$a > b . c > $o";
+        let (tokens, lerrors) = lex(source);
+        assert!(lerrors.is_empty());
+        assert_eq!(tokens[0].kind(), TokenKind::Port);
+        assert_eq!(tokens[1].position(), SourcePosition::new(0, 1));
+        let (connections, perrors) = parse(tokens, false);
+        assert!(perrors.is_empty());
+        assert_eq!(connections.len(), 3);
+        assert_eq!(connections[0].from.kind, IdentKind::InPort);
+        assert!(!connections[1].is_charge);
+        let tr = translate(connections, true);
+        assert_eq!(tr.module, compile(source, false, false).module.unwrap());
+        assert_eq!(tr.names.unwrap(), vec!["a", "b", "c", "o"]);
+    }
 }
@@ -0,0 +1,108 @@
+use crate::{
+    ast::{Ast, Ident, Operator},
+    parse::ParserError,
+    translate::{Connection, IdentKind, Identifier},
+};
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct Lowerer {
+    connections: Vec<Connection>,
+    errors: Vec<ParserError>,
+    kinds: HashMap<String, IdentKind>,
+}
+
+/// Flattens every statement into pairwise connections and checks that each
+/// identifier keeps one kind and that output ports are never blocked. With
+/// `io_min`, a source without at least one input and one output port is an
+/// error.
+pub fn lower(ast: &Ast, io_min: bool) -> (Vec<Connection>, Vec<ParserError>) {
+    Lowerer::default().lower(ast, io_min, true)
+}
+
+/// Lowers `ast`, only checking the whole-source rules when `complete`.
+pub(crate) fn lower_partial(
+    ast: &Ast,
+    io_min: bool,
+    complete: bool,
+) -> (Vec<Connection>, Vec<ParserError>) {
+    Lowerer::default().lower(ast, io_min, complete)
+}
+
+impl Lowerer {
+    fn lower(
+        mut self,
+        ast: &Ast,
+        io_min: bool,
+        complete: bool,
+    ) -> (Vec<Connection>, Vec<ParserError>) {
+        for statement in ast.statements.iter() {
+            let mut from = &statement.head;
+            for link in statement.links.iter() {
+                for source in from.idents.iter() {
+                    for target in link.batch.idents.iter() {
+                        self.connect(source, target, link.operator == Operator::Charge);
+                    }
+                }
+                from = &link.batch;
+            }
+        }
+        if complete && self.errors.is_empty() {
+            if io_min && !self.check_io_min() {
+                self.errors.push(ParserError::IOMin);
+            }
+            self.check_output_block();
+        }
+        (self.connections, self.errors)
+    }
+
+    fn connect(&mut self, from: &Ident, to: &Ident, is_charge: bool) {
+        let from_kind = if from.is_port {
+            IdentKind::InPort
+        } else {
+            IdentKind::Node
+        };
+        let to_kind = if to.is_port {
+            IdentKind::OutPort
+        } else {
+            IdentKind::Node
+        };
+        self.check_ident_kind(&from.name, from_kind);
+        self.check_ident_kind(&to.name, to_kind);
+        let connection = Connection::new(
+            Identifier::new(from.name.clone(), from_kind),
+            Identifier::new(to.name.clone(), to_kind),
+            is_charge,
+        );
+        self.connections
+            .push(connection.with_lines(from.start.line(), to.start.line()));
+    }
+
+    fn check_output_block(&mut self) {
+        for con in self.connections.iter() {
+            if con.to.kind == IdentKind::OutPort && !con.is_charge {
+                self.errors
+                    .push(ParserError::OutPortBlock(con.to.name.clone()));
+            }
+        }
+    }
+
+    fn check_io_min(&self) -> bool {
+        let kinds: Vec<IdentKind> = self.kinds.values().copied().collect();
+        kinds.contains(&IdentKind::InPort) && kinds.contains(&IdentKind::OutPort)
+    }
+
+    fn check_ident_kind(&mut self, name: &str, kind: IdentKind) {
+        match self.kinds.get(name).copied() {
+            Some(act_kind) => {
+                if kind != act_kind {
+                    self.errors
+                        .push(ParserError::InconstIdKind(name.to_owned(), kind, act_kind));
+                }
+            }
+            None => {
+                self.kinds.insert(name.to_owned(), kind);
+            }
+        }
+    }
+}
@@ -15,25 +15,22 @@ pub struct DefaultInverter {
     stack: Vec<Token>,
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Default)]
 enum InverterState {
+    #[default]
     Normal,
     WasPort,
     WasIdent,
     WasEndl(Token),
 }
 
-pub fn consume_end(tokens: &mut Vec<Token>, index: &mut usize) {
-    loop {
-        if let Some(token) = tokens.get(*index) {
-            let t = token.kind();
-            if t == TokenKind::Semicolon || t == TokenKind::EndLine {
-                break;
-            } else {
-                *index += 1;
-            }
-        } else {
+pub fn consume_end(tokens: &[Token], index: &mut usize) {
+    while let Some(token) = tokens.get(*index) {
+        let t = token.kind();
+        if t == TokenKind::Semicolon || t == TokenKind::EndLine {
             break;
+        } else {
+            *index += 1;
         }
     }
 }
@@ -47,11 +44,9 @@ impl DefaultInverter {
                 self.state = InverterState::Normal;
                 self.stack.push(token);
             }
-            TokenKind::Space => {
-                if self.state == InverterState::WasPort {
-                    self.stack.push(token);
-                    self.state = InverterState::Normal;
-                }
+            TokenKind::Space if self.state == InverterState::WasPort => {
+                self.stack.push(token);
+                self.state = InverterState::Normal;
             }
             TokenKind::Port => {
                 self.state = InverterState::WasPort;
@@ -59,16 +54,13 @@ impl DefaultInverter {
             }
             TokenKind::Identifier => {
                 self.stack.push(token);
-                match &self.state {
-                    InverterState::WasEndl(endl) => self.stack.push(endl.clone()),
-                    _ => {}
+                if let InverterState::WasEndl(endl) = &self.state {
+                    self.stack.push(endl.clone())
                 }
                 self.state = InverterState::WasIdent;
             }
-            TokenKind::EndLine => {
-                if self.state == InverterState::WasIdent {
-                    self.state = InverterState::WasEndl(token);
-                }
+            TokenKind::EndLine if self.state == InverterState::WasIdent => {
+                self.state = InverterState::WasEndl(token);
             }
             _ => {}
         }
@@ -90,7 +82,7 @@ impl Inverter for DefaultInverter {
                 return;
             }
         }
-        consume_end(&mut self.tokens, &mut self.index);
+        consume_end(&self.tokens, &mut self.index);
         self.state = InverterState::Normal;
     }
     fn expect(&mut self) -> Option<Token> {
@@ -102,13 +94,7 @@ impl Inverter for DefaultInverter {
         while self.index < self.tokens.len() && self.stack.is_empty() {
             self.get();
         }
-        return self.stack.last().cloned();
-    }
-}
-
-impl Default for InverterState {
-    fn default() -> Self {
-        InverterState::Normal
+        self.stack.last().cloned()
     }
 }
 
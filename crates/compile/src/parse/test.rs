@@ -1,8 +1,10 @@
 use crate::{
-    lex::{SourcePosition, Token},
+    ast::Operator,
+    lex::{lex, SourcePosition, Token},
+    lower::lower,
     parse::{
         inverter::{consume_end, Inverter},
-        Parser, ParserError,
+        parse_ast, Parser, ParserError,
     },
     translate::{ConVec, Connection, IdentKind},
 };
@@ -26,7 +28,7 @@ impl Inverter for MockInverter {
         Some(t)
     }
     fn consume_end(&mut self) {
-        consume_end(&mut self.tokens, &mut self.index)
+        consume_end(&self.tokens, &mut self.index)
     }
 }
 
@@ -466,3 +468,51 @@ fn outport_block_violation() {
         vec![ParserError::OutPortBlock("b".to_owned())],
     )
 }
+
+#[test]
+fn ast_keeps_statement_structure() {
+    let (tokens, _) = lex("a, $b > c . d\n  x > $y");
+    let (ast, errors) = parse_ast(tokens);
+    assert_eq!(errors, vec![]);
+    assert_eq!(ast.statements.len(), 2);
+    let first = &ast.statements[0];
+    assert_eq!(first.head.idents.len(), 2);
+    assert!(first.head.idents[1].is_port);
+    assert_eq!(first.head.idents[1].start, SourcePosition::new(0, 3));
+    assert_eq!(first.head.idents[1].end, SourcePosition::new(0, 5));
+    assert_eq!(first.links[0].operator, Operator::Charge);
+    assert_eq!(first.links[1].operator, Operator::Block);
+    assert_eq!(first.links[1].position, SourcePosition::new(0, 10));
+    assert_eq!(first.start(), SourcePosition::new(0, 0));
+    assert_eq!(first.end(), SourcePosition::new(0, 13));
+    assert_eq!(ast.statements[1].start(), SourcePosition::new(1, 2));
+    assert_eq!(ast.statements[1].batches().count(), 2);
+}
+
+#[test]
+fn ast_skips_broken_statements() {
+    let (tokens, _) = lex("a > b > .\nc > d");
+    let (ast, errors) = parse_ast(tokens);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(ast.statements.len(), 1);
+    assert_eq!(ast.statements[0].head.idents[0].name, "c");
+}
+
+#[test]
+fn lowering_flattens_batches() {
+    let (tokens, _) = lex("a, b > c, d . e");
+    let (ast, _) = parse_ast(tokens);
+    let (connections, errors) = lower(&ast, false);
+    assert_eq!(errors, vec![]);
+    assert_eq!(
+        ConVec(connections),
+        ConVec(vec![
+            connection!(a > c),
+            connection!(a > d),
+            connection!(b > c),
+            connection!(b > d),
+            connection!(c.e),
+            connection!(d.e),
+        ])
+    );
+}
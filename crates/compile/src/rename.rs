@@ -0,0 +1,85 @@
+use crate::lex::{lex, LexerError, TokenKind};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RenameError {
+    /// The source does not lex, so it cannot be rebuilt from its tokens.
+    Lexer(Vec<LexerError>),
+    /// The new name is not a plain identifier.
+    InvalidName(String),
+    /// No identifier of the source has the old name.
+    Undefined(String),
+    /// The new name is already taken.
+    Collision(String),
+}
+
+/// Renames every identifier called `from` to `to`, leaving comments,
+/// spacing and every other name untouched.
+///
+/// The kind of an identifier follows from its `$` and where it appears, so
+/// a rename keeps every kind as long as it does not merge two names. Renames
+/// onto an existing name are refused, as are new names that would not lex
+/// as a single identifier, like `mod` or `1a`.
+pub fn rename(source: &str, from: &str, to: &str) -> Result<String, RenameError> {
+    let (tokens, errors) = lex(source);
+    if !errors.is_empty() {
+        return Err(RenameError::Lexer(errors));
+    }
+    let (name, errors) = lex(to);
+    if !errors.is_empty() || name.len() != 1 || name[0].kind() != TokenKind::Identifier {
+        return Err(RenameError::InvalidName(to.to_owned()));
+    }
+    let is_name = |text: &str| {
+        tokens
+            .iter()
+            .any(|token| token.kind() == TokenKind::Identifier && token.text() == text)
+    };
+    if !is_name(from) {
+        return Err(RenameError::Undefined(from.to_owned()));
+    }
+    if from != to && is_name(to) {
+        return Err(RenameError::Collision(to.to_owned()));
+    }
+    Ok(tokens
+        .iter()
+        .map(|token| {
+            if token.kind() == TokenKind::Identifier && token.text() == from {
+                to
+            } else {
+                token.text()
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::rename::{rename, RenameError};
+
+    #[test]
+    fn keeps_layout_and_longer_names() {
+        let source = "$a > ab # a > ab\n  ab ,a . $o;a>o\n";
+        assert_eq!(
+            rename(source, "a", "in").unwrap(),
+            "$in > ab # a > ab\n  ab ,in . $o;in>o\n"
+        );
+    }
+
+    #[test]
+    fn refuses_collisions_and_invalid_names() {
+        let source = "$a > b\nb > $o";
+        assert_eq!(
+            rename(source, "a", "b"),
+            Err(RenameError::Collision("b".to_owned()))
+        );
+        assert_eq!(
+            rename(source, "c", "d"),
+            Err(RenameError::Undefined("c".to_owned()))
+        );
+        for name in ["mod", "1a", "a b", "$c", ""].iter() {
+            assert_eq!(
+                rename(source, "b", name),
+                Err(RenameError::InvalidName(name.to_string()))
+            );
+        }
+    }
+}
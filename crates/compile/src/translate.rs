@@ -8,11 +8,14 @@ struct Translator {
     indexes: IndexMap,
 }
 
-#[derive(PartialEq, Eq, Clone)]
+/// One charging or blocking edge between two named nodes.
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub struct Connection {
     pub from: Identifier,
     pub to: Identifier,
     pub is_charge: bool,
+    from_line: usize,
+    to_line: usize,
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -24,17 +27,35 @@ pub struct Identifier {
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum IdentKind {
     Node,
+    /// A `$` identifier on the source side of a connection.
     InPort,
+    /// A `$` identifier on the target side of a connection.
     OutPort,
 }
 
+/// The module built by `translate`. Symbols are only filled in when asked
+/// for.
 pub struct TranslationResult {
     pub module: Module,
+    /// Names of the inputs and of the outputs, by position.
     pub identifiers: Option<(Vec<String>, Vec<String>)>,
+    /// Names of the nodes, by index.
+    pub names: Option<Vec<String>>,
+    pub source_map: Option<SourceMap>,
+}
+
+/// Source lines, counted from zero, where nodes and edges first appear. An
+/// edge is placed on the line of its target.
+#[derive(Default, PartialEq, Eq, Debug)]
+pub struct SourceMap {
+    pub nodes: Vec<usize>,
+    /// Keyed by source node, target node and whether the edge charges.
+    pub edges: HashMap<(usize, usize, bool), usize>,
 }
 
+#[cfg(test)]
 #[derive(Default, PartialEq, Eq)]
-pub struct ConVec(pub Vec<Connection>);
+pub(crate) struct ConVec(pub Vec<Connection>);
 
 #[allow(unused_macros)]
 macro_rules! connection {
@@ -144,6 +165,7 @@ macro_rules! connection {
     };
 }
 
+#[cfg(test)]
 impl Debug for ConVec {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for c in self.0.iter() {
@@ -170,6 +192,8 @@ impl Debug for ConVec {
     }
 }
 
+/// Numbers the nodes in order of first appearance and builds the module, the
+/// last stage of `compile`. With `idents`, the symbols are collected too.
 pub fn translate(connections: Vec<Connection>, idents: bool) -> TranslationResult {
     Translator::default().translate(connections, idents)
 }
@@ -179,9 +203,20 @@ impl Translator {
         let mut input_ids = vec![];
         let mut output_ids = vec![];
         let mut builder = ModuleBuilder::default();
+        let mut source_map = SourceMap::default();
         for con in connections.iter() {
             let (from_idx, from_new) = self.index(&con.from);
             let (to_idx, to_new) = self.index(&con.to);
+            if from_new {
+                source_map.nodes.push(con.from_line);
+            }
+            if to_new {
+                source_map.nodes.push(con.to_line);
+            }
+            source_map
+                .edges
+                .entry((from_idx, to_idx, con.is_charge))
+                .or_insert(con.to_line);
 
             builder.connect(from_idx, to_idx, con.is_charge);
             if from_new && con.from.kind == IdentKind::InPort {
@@ -204,9 +239,19 @@ impl Translator {
             } else {
                 None
             },
+            names: if idents { Some(self.names()) } else { None },
+            source_map: if idents { Some(source_map) } else { None },
         }
     }
 
+    fn names(&self) -> Vec<String> {
+        let mut names = vec![String::new(); self.indexes.len()];
+        for (name, &index) in self.indexes.iter() {
+            names[index] = name.clone();
+        }
+        names
+    }
+
     fn index(&mut self, ident: &Identifier) -> (usize, bool) {
         match self.indexes.get(&ident.name).copied() {
             Some(index) => (index, false),
@@ -224,8 +269,29 @@ impl Connection {
             from,
             to,
             is_charge,
+            from_line: 0,
+            to_line: 0,
+        }
+    }
+
+    /// The same connection, placed on the given source lines.
+    pub fn with_lines(self, from_line: usize, to_line: usize) -> Connection {
+        Connection {
+            from_line,
+            to_line,
+            ..self
         }
     }
+
+    /// Source line of the source identifier, or 0 if not placed.
+    pub fn from_line(&self) -> usize {
+        self.from_line
+    }
+
+    /// Source line of the target identifier, or 0 if not placed.
+    pub fn to_line(&self) -> usize {
+        self.to_line
+    }
 }
 
 impl Identifier {
@@ -349,6 +415,28 @@ mod test {
         )
     }
 
+    #[test]
+    fn list_node_names() {
+        let translation_result = translate(
+            vec![connection!(!e > m), connection!(m > !o), connection!(!i.m)],
+            true,
+        );
+        assert_eq!(translation_result.names.unwrap(), vec!["e", "m", "o", "i"]);
+    }
+
+    #[test]
+    fn map_lines() {
+        let first = connection!(a > b).with_lines(1, 2);
+        let second = connection!(b.c).with_lines(2, 3);
+        let repeated = connection!(a > b).with_lines(5, 5);
+        let source_map = translate(vec![first, second, repeated], true)
+            .source_map
+            .unwrap();
+        assert_eq!(source_map.nodes, vec![1, 2, 3]);
+        assert_eq!(source_map.edges[&(0, 1, true)], 2);
+        assert_eq!(source_map.edges[&(1, 2, false)], 3);
+    }
+
     #[test]
     fn list_inputs_outputs() {
         translate_test_case_ids(
@@ -0,0 +1,523 @@
+use crate::{Module, ModuleBuilder};
+use std::collections::BTreeSet;
+
+/// Edges as (from, to, is_charge), sorted.
+type Edges = Vec<(usize, usize, bool)>;
+
+/// Renumbers the nodes of `module` so that isomorphic modules come out
+/// equal. Inputs keep their order and come first, then outputs in order,
+/// then internal nodes in an order that only depends on the structure.
+pub fn canonical(module: &Module) -> Module {
+    let labels = canonical_labels(module);
+    let mut builder = ModuleBuilder::default();
+    for (from, to, is_charge) in relabel(module, &labels) {
+        builder.connect(from, to, is_charge);
+    }
+    for &input in module.inputs.iter() {
+        builder.input(labels[input]);
+    }
+    for &output in module.outputs.iter() {
+        builder.output(labels[output]);
+    }
+    let mut canonical = builder.build();
+    canonical
+        .connections
+        .resize_with(module.connections.len(), Default::default);
+    canonical
+}
+
+/// Whether the two modules are equal up to the numbering of their nodes,
+/// with every input and output kept at its position.
+pub fn is_isomorphic(left: &Module, right: &Module) -> bool {
+    left.connections.len() == right.connections.len()
+        && left.inputs.len() == right.inputs.len()
+        && left.outputs.len() == right.outputs.len()
+        && canonical(left) == canonical(right)
+}
+
+/// The new index of every node in the canonical form.
+///
+/// Nodes are first told apart by partition refinement, starting from one
+/// class per port and one for all internal nodes. Nodes refinement cannot separate
+/// are singled out one at a time, trying each member of the first tied class
+/// and keeping the numbering with the smallest edge list. Numberings that tie
+/// reveal symmetries of the module, which are used to skip candidates that
+/// would only repeat earlier work.
+pub fn canonical_labels(module: &Module) -> Vec<usize> {
+    let mut search = Search {
+        graph: Graph::new(module),
+        first: None,
+        best: None,
+        automorphisms: vec![],
+    };
+    let partition = Partition::new(module);
+    let splitters = partition.starts();
+    search.search(partition, splitters, &mut vec![]);
+    search.best.unwrap().0
+}
+
+struct Graph {
+    /// (target, is_charge) per node.
+    successors: Vec<Vec<(usize, bool)>>,
+    /// (source, is_charge) per node.
+    predecessors: Vec<Vec<(usize, bool)>>,
+}
+
+struct Search {
+    graph: Graph,
+    /// Path, labels and edges of the first leaf.
+    first: Option<(Vec<usize>, Vec<usize>, Edges)>,
+    /// Labels and edges of the smallest leaf so far.
+    best: Option<(Vec<usize>, Edges)>,
+    automorphisms: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    fn new(module: &Module) -> Graph {
+        let count = module.connections.len();
+        let mut successors = vec![vec![]; count];
+        let mut predecessors = vec![vec![]; count];
+        for (from, node) in module.connections.iter().enumerate() {
+            for (targets, is_charge) in [(&node.charging, true), (&node.blocking, false)].iter() {
+                for &to in targets.iter() {
+                    successors[from].push((to, *is_charge));
+                    predecessors[to].push((from, *is_charge));
+                }
+            }
+        }
+        Graph {
+            successors,
+            predecessors,
+        }
+    }
+}
+
+impl Search {
+    /// Returns the depth to unwind to when a whole subtree turned out to
+    /// repeat one explored before.
+    fn search(
+        &mut self,
+        mut partition: Partition,
+        splitters: Vec<usize>,
+        path: &mut Vec<usize>,
+    ) -> Option<usize> {
+        partition.refine(&self.graph, splitters);
+        let cell = match partition.target_cell() {
+            Some(cell) => cell,
+            None => return self.leaf(partition.position, path),
+        };
+        let mut tried: Vec<usize> = vec![];
+        for node in cell {
+            let orbits = self.orbits(path);
+            if tried.iter().any(|&other| orbits[other] == orbits[node]) {
+                continue;
+            }
+            tried.push(node);
+            path.push(node);
+            let mut individualized = partition.clone();
+            let splitter = individualized.individualize(node);
+            let unwind = self.search(individualized, vec![splitter], path);
+            path.pop();
+            if let Some(depth) = unwind {
+                if depth < path.len() {
+                    return unwind;
+                }
+            }
+        }
+        None
+    }
+
+    fn leaf(&mut self, labels: Vec<usize>, path: &[usize]) -> Option<usize> {
+        let edges = relabel_graph(&self.graph, &labels);
+        let (first_path, first_labels, first_edges) = match &self.first {
+            None => {
+                self.first = Some((path.to_vec(), labels.clone(), edges.clone()));
+                self.best = Some((labels, edges));
+                return None;
+            }
+            Some(first) => first,
+        };
+        if edges == *first_edges {
+            // The subtree where this path leaves the first one mirrors the
+            // first path's, so there is nothing left to find in it.
+            let depth = first_path
+                .iter()
+                .zip(path)
+                .take_while(|(a, b)| a == b)
+                .count();
+            self.automorphisms.push(automorphism(first_labels, &labels));
+            return Some(depth);
+        }
+        let (best_labels, best_edges) = self.best.as_ref().unwrap();
+        if edges == *best_edges {
+            self.automorphisms.push(automorphism(best_labels, &labels));
+        } else if edges < *best_edges {
+            self.best = Some((labels, edges));
+        }
+        None
+    }
+
+    /// Orbit representative of every node under the known automorphisms that
+    /// fix every node of `path`.
+    fn orbits(&self, path: &[usize]) -> Vec<usize> {
+        let mut parent: Vec<usize> = (0..self.graph.successors.len()).collect();
+        for automorphism in self.automorphisms.iter() {
+            if path.iter().all(|&node| automorphism[node] == node) {
+                for (node, &image) in automorphism.iter().enumerate() {
+                    let (a, b) = (find(&mut parent, node), find(&mut parent, image));
+                    parent[a.max(b)] = a.min(b);
+                }
+            }
+        }
+        (0..parent.len())
+            .map(|node| find(&mut parent, node))
+            .collect()
+    }
+}
+
+/// An ordered partition of the nodes into classes. Each class is a run of
+/// `order`, and the position where it starts serves as its colour, so a
+/// partition where every class is a single node numbers the nodes.
+#[derive(Clone)]
+struct Partition {
+    /// The nodes, class by class.
+    order: Vec<usize>,
+    /// Where each node is in `order`.
+    position: Vec<usize>,
+    /// Start of the class of each node.
+    start: Vec<usize>,
+    /// Size of each class, at its start.
+    size: Vec<usize>,
+}
+
+impl Partition {
+    /// One class per port, inputs then outputs, then one for the rest.
+    fn new(module: &Module) -> Partition {
+        let count = module.connections.len();
+        let mut keys = vec![(2, 0); count];
+        for (position, &output) in module.outputs.iter().enumerate().rev() {
+            keys[output] = (1, position);
+        }
+        for (position, &input) in module.inputs.iter().enumerate().rev() {
+            keys[input] = (0, position);
+        }
+        let mut order: Vec<usize> = (0..count).collect();
+        order.sort_by_key(|&node| keys[node]);
+        let mut partition = Partition {
+            position: vec![0; count],
+            start: vec![0; count],
+            size: vec![0; count],
+            order,
+        };
+        let mut start = 0;
+        for position in 0..count {
+            let node = partition.order[position];
+            if keys[node] != keys[partition.order[start]] {
+                start = position;
+            }
+            partition.position[node] = position;
+            partition.start[node] = start;
+            partition.size[start] += 1;
+        }
+        partition
+    }
+
+    fn starts(&self) -> Vec<usize> {
+        let mut starts = vec![];
+        let mut start = 0;
+        while start < self.order.len() {
+            starts.push(start);
+            start += self.size[start];
+        }
+        starts
+    }
+
+    /// Splits classes by how many edges of each kind their nodes have into
+    /// the splitter classes, until the partition is equitable: nodes of a
+    /// class have as many edges of each kind into any other class.
+    ///
+    /// This is Hopcroft's scheme. A split class hands its fragments to the
+    /// worklist, all of them if it was waiting there itself and all but the
+    /// largest otherwise, and only the nodes with edges into the splitter
+    /// are touched. Splitters are taken in order of position and fragments
+    /// are ordered by edge count, so the outcome does not depend on how the
+    /// nodes were numbered.
+    fn refine(&mut self, graph: &Graph, splitters: Vec<usize>) {
+        let mut waiting: BTreeSet<usize> = splitters.into_iter().collect();
+        let mut counts = vec![0; self.order.len()];
+        while let Some(splitter) = waiting.iter().next().copied() {
+            waiting.remove(&splitter);
+            let members = self.order[splitter..splitter + self.size[splitter]].to_vec();
+            for kind in 0..4 {
+                let mut touched = vec![];
+                for &member in members.iter() {
+                    let (edges, is_charge) = match kind {
+                        0 => (&graph.predecessors[member], true),
+                        1 => (&graph.predecessors[member], false),
+                        2 => (&graph.successors[member], true),
+                        _ => (&graph.successors[member], false),
+                    };
+                    for &(node, charge) in edges.iter() {
+                        if charge == is_charge {
+                            if counts[node] == 0 {
+                                touched.push(node);
+                            }
+                            counts[node] += 1;
+                        }
+                    }
+                }
+                touched.sort_unstable_by_key(|&node| self.start[node]);
+                let mut first = 0;
+                while first < touched.len() {
+                    let start = self.start[touched[first]];
+                    let last = first
+                        + touched[first..]
+                            .iter()
+                            .take_while(|&&node| self.start[node] == start)
+                            .count();
+                    self.split(start, &touched[first..last], &counts, &mut waiting);
+                    first = last;
+                }
+                for node in touched {
+                    counts[node] = 0;
+                }
+            }
+        }
+    }
+
+    /// Splits the class at `start` by `counts`, given its nodes with a
+    /// nonzero count. Nodes with a count of zero stay at the front.
+    fn split(
+        &mut self,
+        start: usize,
+        touched: &[usize],
+        counts: &[usize],
+        waiting: &mut BTreeSet<usize>,
+    ) {
+        let size = self.size[start];
+        let mut touched = touched.to_vec();
+        touched.sort_unstable_by_key(|&node| counts[node]);
+        if touched.len() == size && counts[touched[0]] == counts[touched[size - 1]] {
+            return;
+        }
+        // Move the touched nodes to the back, in order of count. A swap only
+        // ever displaces a node that has not been placed yet.
+        let back = start + size - touched.len();
+        for (offset, &node) in touched.iter().enumerate() {
+            let (from, to) = (self.position[node], back + offset);
+            let other = self.order[to];
+            self.order.swap(from, to);
+            self.position[other] = from;
+            self.position[node] = to;
+        }
+        let mut fragments = vec![];
+        if back > start {
+            fragments.push((start, back - start));
+        }
+        let mut first = 0;
+        while first < touched.len() {
+            let count = counts[touched[first]];
+            let length = touched[first..]
+                .iter()
+                .take_while(|&&node| counts[node] == count)
+                .count();
+            fragments.push((back + first, length));
+            first += length;
+        }
+        // The untouched nodes keep their start, so only the touched ones are
+        // visited.
+        for &(fragment, length) in fragments.iter() {
+            self.size[fragment] = length;
+            if fragment >= back {
+                for &node in self.order[fragment..fragment + length].iter() {
+                    self.start[node] = fragment;
+                }
+            }
+        }
+        let largest = if waiting.contains(&start) {
+            None
+        } else {
+            fragments
+                .iter()
+                .enumerate()
+                .max_by_key(|&(index, &(_, length))| (length, std::cmp::Reverse(index)))
+                .map(|(index, _)| index)
+        };
+        for (index, &(fragment, _)) in fragments.iter().enumerate() {
+            if Some(index) != largest {
+                waiting.insert(fragment);
+            }
+        }
+    }
+
+    /// Members of the first class with several nodes, sorted by index, or
+    /// `None` if every node has a class of its own.
+    fn target_cell(&self) -> Option<Vec<usize>> {
+        let start = self
+            .starts()
+            .into_iter()
+            .find(|&start| self.size[start] > 1)?;
+        let mut cell = self.order[start..start + self.size[start]].to_vec();
+        cell.sort_unstable();
+        Some(cell)
+    }
+
+    /// Gives `node` a class of its own, just after the rest of its class,
+    /// and returns the position of the new class.
+    fn individualize(&mut self, node: usize) -> usize {
+        let start = self.start[node];
+        let last = start + self.size[start] - 1;
+        let (from, other) = (self.position[node], self.order[last]);
+        self.order.swap(from, last);
+        self.position[other] = from;
+        self.position[node] = last;
+        self.size[start] -= 1;
+        self.size[last] = 1;
+        self.start[node] = last;
+        last
+    }
+}
+
+fn relabel(module: &Module, labels: &[usize]) -> Edges {
+    relabel_graph(&Graph::new(module), labels)
+}
+
+fn relabel_graph(graph: &Graph, labels: &[usize]) -> Edges {
+    let mut edges: Edges = graph
+        .successors
+        .iter()
+        .enumerate()
+        .flat_map(|(from, targets)| {
+            targets
+                .iter()
+                .map(move |&(to, is_charge)| (labels[from], labels[to], is_charge))
+        })
+        .collect();
+    edges.sort_unstable();
+    edges
+}
+
+/// The permutation taking each node of one leaf to the node with the same
+/// label in another.
+fn automorphism(from: &[usize], to: &[usize]) -> Vec<usize> {
+    let mut by_label = vec![0; to.len()];
+    for (node, &label) in to.iter().enumerate() {
+        by_label[label] = node;
+    }
+    from.iter().map(|&label| by_label[label]).collect()
+}
+
+fn find(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        canonical::{canonical, is_isomorphic},
+        Module, ModuleBuilder,
+    };
+
+    fn module(inputs: &[usize], outputs: &[usize], edges: &[(usize, usize, bool)]) -> Module {
+        let mut builder = ModuleBuilder::default();
+        for &(from, to, is_charge) in edges {
+            builder.connect(from, to, is_charge);
+        }
+        for &input in inputs {
+            builder.input(input);
+        }
+        for &output in outputs {
+            builder.output(output);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn renumbered_modules_are_isomorphic() {
+        // $a > x > y > $o; $b . y, with the internal nodes swapped around.
+        let left = module(
+            &[0, 1],
+            &[4],
+            &[(0, 2, true), (2, 3, true), (3, 4, true), (1, 3, false)],
+        );
+        let right = module(
+            &[4, 0],
+            &[1],
+            &[(4, 3, true), (3, 2, true), (2, 1, true), (0, 2, false)],
+        );
+        assert_ne!(left, right);
+        assert!(is_isomorphic(&left, &right));
+        assert_eq!(canonical(&left), canonical(&right));
+        assert_eq!(canonical(&left).inputs, vec![0, 1]);
+    }
+
+    #[test]
+    fn ports_keep_their_positions() {
+        // Swapping which input blocks the path changes the circuit.
+        let left = module(&[0, 1], &[3], &[(0, 2, true), (2, 3, true), (1, 2, false)]);
+        let right = module(&[0, 1], &[3], &[(1, 2, true), (2, 3, true), (0, 2, false)]);
+        assert!(!is_isomorphic(&left, &right));
+        let charged = module(&[0, 1], &[3], &[(0, 2, true), (2, 3, true), (1, 2, true)]);
+        assert!(!is_isomorphic(&left, &charged));
+    }
+
+    #[test]
+    fn symmetric_modules() {
+        // Many identical parallel chains and a ring whose nodes all look
+        // alike, numbered in two different orders.
+        let chains = 30;
+        let ring = 40;
+        let build = |shift: usize| {
+            let internal = |node: usize| 2 + (node + shift) % (2 * chains + ring);
+            let mut edges = vec![];
+            for chain in 0..chains {
+                let (x, y) = (internal(2 * chain), internal(2 * chain + 1));
+                edges.extend([(0, x, true), (x, y, true), (y, 1, true)].iter());
+            }
+            for node in 0..ring {
+                let from = internal(2 * chains + node);
+                let to = internal(2 * chains + (node + 1) % ring);
+                edges.push((from, to, true));
+            }
+            module(&[0], &[1], &edges)
+        };
+        assert!(is_isomorphic(&build(0), &build(7)));
+    }
+
+    #[test]
+    fn regular_modules_that_differ() {
+        // One ring of six against two rings of three: every node has one
+        // charger and one target in both, so only the search tells them apart.
+        let ring = |cycles: &[&[usize]]| {
+            let mut edges = vec![];
+            for cycle in cycles {
+                for (position, &node) in cycle.iter().enumerate() {
+                    edges.push((node, cycle[(position + 1) % cycle.len()], true));
+                }
+            }
+            module(&[], &[], &edges)
+        };
+        let one = ring(&[&[0, 1, 2, 3, 4, 5]]);
+        let two = ring(&[&[0, 2, 4], &[1, 3, 5]]);
+        assert!(!is_isomorphic(&one, &two));
+        assert!(is_isomorphic(&two, &ring(&[&[5, 0, 1], &[4, 3, 2]])));
+    }
+
+    #[test]
+    fn long_chain() {
+        let chain = |reversed: bool| {
+            let mut builder = ModuleBuilder::default();
+            let node = |index: usize| if reversed { 100_000 - index } else { index };
+            for index in 0..100_000 {
+                builder.charge(node(index), node(index + 1));
+            }
+            builder.block(node(50_000), node(3));
+            builder.build()
+        };
+        assert!(is_isomorphic(&chain(false), &chain(true)));
+    }
+}
@@ -1,12 +1,17 @@
 use std::collections::HashSet;
 
-#[derive(Default, PartialEq, Eq, Debug)]
+pub mod canonical;
+pub mod scc;
+pub mod stats;
+pub mod timing;
+
+#[derive(Default, PartialEq, Eq, Debug, Clone)]
 pub struct NodeConnections {
     pub charging: HashSet<usize>,
     pub blocking: HashSet<usize>,
 }
 
-#[derive(Default, PartialEq, Eq, Debug)]
+#[derive(Default, PartialEq, Eq, Debug, Clone)]
 pub struct Module {
     pub connections: Vec<NodeConnections>,
     pub inputs: Vec<usize>,
@@ -51,6 +56,6 @@ impl ModuleBuilder {
         self.module.outputs.len() - 1
     }
     pub fn build(&mut self) -> Module {
-        std::mem::replace(&mut self.module, Module::default())
+        std::mem::take(&mut self.module)
     }
 }
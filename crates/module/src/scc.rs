@@ -0,0 +1,141 @@
+use crate::Module;
+
+/// Strongly connected components of the graph of charging and blocking
+/// edges. Members are sorted by index and components by their first member.
+pub fn components(module: &Module) -> Vec<Vec<usize>> {
+    let successors: Vec<Vec<usize>> = module
+        .connections
+        .iter()
+        .map(|node| {
+            let mut targets: Vec<usize> = node.charging.union(&node.blocking).copied().collect();
+            targets.sort_unstable();
+            targets
+        })
+        .collect();
+    let mut tarjan = Tarjan {
+        successors: &successors,
+        index: vec![None; successors.len()],
+        low: vec![0; successors.len()],
+        on_stack: vec![false; successors.len()],
+        stack: vec![],
+        next: 0,
+        components: vec![],
+    };
+    for node in 0..successors.len() {
+        if tarjan.index[node].is_none() {
+            tarjan.visit(node);
+        }
+    }
+    let mut components = tarjan.components;
+    for component in components.iter_mut() {
+        component.sort_unstable();
+    }
+    components.sort_unstable();
+    components
+}
+
+/// Components that hold a feedback loop: those with more than one node, and
+/// single nodes with an edge to themselves.
+pub fn loops(module: &Module) -> Vec<Vec<usize>> {
+    components(module)
+        .into_iter()
+        .filter(|component| component.len() > 1 || has_self_loop(module, component[0]))
+        .collect()
+}
+
+pub fn has_self_loop(module: &Module, node: usize) -> bool {
+    let connections = &module.connections[node];
+    connections.charging.contains(&node) || connections.blocking.contains(&node)
+}
+
+struct Tarjan<'a> {
+    successors: &'a [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    on_stack: Vec<bool>,
+    stack: Vec<usize>,
+    next: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    /// Tarjan's algorithm with an explicit call stack of (node, position of
+    /// the next successor to look at), so deep chains cannot overflow.
+    fn visit(&mut self, root: usize) {
+        let mut calls = vec![(root, 0)];
+        self.open(root);
+        while let Some(&(node, position)) = calls.last() {
+            if let Some(&target) = self.successors[node].get(position) {
+                calls.last_mut().unwrap().1 += 1;
+                match self.index[target] {
+                    None => {
+                        self.open(target);
+                        calls.push((target, 0));
+                    }
+                    Some(index) if self.on_stack[target] => {
+                        self.low[node] = self.low[node].min(index);
+                    }
+                    Some(_) => {}
+                }
+            } else {
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    self.low[parent] = self.low[parent].min(self.low[node]);
+                }
+                if Some(self.low[node]) == self.index[node] {
+                    let mut component = vec![];
+                    loop {
+                        let member = self.stack.pop().unwrap();
+                        self.on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    self.components.push(component);
+                }
+            }
+        }
+    }
+
+    fn open(&mut self, node: usize) {
+        self.index[node] = Some(self.next);
+        self.low[node] = self.next;
+        self.next += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        scc::{components, loops},
+        ModuleBuilder,
+    };
+
+    #[test]
+    fn finds_rings_and_self_loops() {
+        // 0 > 1 > 2 > 0 is a ring, 3 blocks itself and 4 hangs off the ring.
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(2, 0);
+        builder.charge(2, 4);
+        builder.block(3, 3);
+        builder.charge(4, 3);
+        let module = builder.build();
+        assert_eq!(components(&module), vec![vec![0, 1, 2], vec![3], vec![4]]);
+        assert_eq!(loops(&module), vec![vec![0, 1, 2], vec![3]]);
+    }
+
+    #[test]
+    fn long_chain_does_not_overflow() {
+        let mut builder = ModuleBuilder::default();
+        for node in 0..100_000 {
+            builder.charge(node, node + 1);
+        }
+        builder.block(100_000, 0);
+        assert_eq!(loops(&builder.build())[0].len(), 100_001);
+    }
+}
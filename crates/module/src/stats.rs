@@ -0,0 +1,117 @@
+use crate::{scc::loops, Module};
+
+/// Size and shape figures of a module.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Stats {
+    pub nodes: usize,
+    pub inputs: usize,
+    pub outputs: usize,
+    pub charging_edges: usize,
+    pub blocking_edges: usize,
+    /// `fan_in[k]` is the number of nodes with `k` incoming edges of either
+    /// kind.
+    pub fan_in: Vec<usize>,
+    /// `fan_out[k]` is the number of nodes with `k` outgoing edges of either
+    /// kind.
+    pub fan_out: Vec<usize>,
+    /// Nodes with the largest fan-out, sorted by index. Empty without edges.
+    pub max_fan_out: Vec<usize>,
+    /// Strongly connected components holding a feedback loop.
+    pub loops: usize,
+    /// Edges from a node to itself.
+    pub self_loops: usize,
+}
+
+pub fn stats(module: &Module) -> Stats {
+    let nodes = module.connections.len();
+    let mut fan_in = vec![0; nodes];
+    let mut fan_out = vec![0; nodes];
+    let mut self_loops = 0;
+    for (from, node) in module.connections.iter().enumerate() {
+        for &to in node.charging.iter().chain(node.blocking.iter()) {
+            fan_in[to] += 1;
+            fan_out[from] += 1;
+            if from == to {
+                self_loops += 1;
+            }
+        }
+    }
+    let most = fan_out.iter().copied().max().unwrap_or(0);
+    let max_fan_out = if most == 0 {
+        vec![]
+    } else {
+        (0..nodes).filter(|&node| fan_out[node] == most).collect()
+    };
+    Stats {
+        nodes,
+        inputs: module.inputs.len(),
+        outputs: module.outputs.len(),
+        charging_edges: module
+            .connections
+            .iter()
+            .map(|node| node.charging.len())
+            .sum(),
+        blocking_edges: module
+            .connections
+            .iter()
+            .map(|node| node.blocking.len())
+            .sum(),
+        fan_in: distribution(&fan_in),
+        fan_out: distribution(&fan_out),
+        max_fan_out,
+        loops: loops(module).len(),
+        self_loops,
+    }
+}
+
+fn distribution(degrees: &[usize]) -> Vec<usize> {
+    let mut counts = vec![0; degrees.iter().copied().max().map_or(0, |most| most + 1)];
+    for &degree in degrees {
+        counts[degree] += 1;
+    }
+    counts
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        stats::{stats, Stats},
+        Module, ModuleBuilder,
+    };
+
+    #[test]
+    fn counts_edges_and_degrees() {
+        // $a > b, c; b . c; c > c > $o
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(0, 2);
+        builder.block(1, 2);
+        builder.charge(2, 2);
+        builder.charge(2, 3);
+        builder.input(0);
+        builder.output(3);
+        assert_eq!(
+            stats(&builder.build()),
+            Stats {
+                nodes: 4,
+                inputs: 1,
+                outputs: 1,
+                charging_edges: 4,
+                blocking_edges: 1,
+                fan_in: vec![1, 2, 0, 1],
+                fan_out: vec![1, 1, 2],
+                max_fan_out: vec![0, 2],
+                loops: 1,
+                self_loops: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn empty_module() {
+        let stats = stats(&Module::default());
+        assert_eq!(stats.nodes, 0);
+        assert_eq!(stats.fan_in, Vec::<usize>::new());
+        assert_eq!(stats.max_fan_out, Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,229 @@
+use crate::Module;
+use std::collections::VecDeque;
+
+/// Charge path delays, in ticks, from one input port to one output port.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PathDelay {
+    /// Position of the input in `Module::inputs`.
+    pub input: usize,
+    /// Position of the output in `Module::outputs`.
+    pub output: usize,
+    pub shortest: usize,
+    pub longest: usize,
+    /// Nodes on some path between the two ports that are targets of a
+    /// blocking edge, sorted by index.
+    pub gates: Vec<usize>,
+}
+
+impl PathDelay {
+    /// Whether every path delivers the charge on the same tick. Unbalanced
+    /// pairs can make the output glitch.
+    pub fn is_balanced(&self) -> bool {
+        self.shortest == self.longest
+    }
+}
+
+/// Computes the delay of every connected input/output pair. Since every
+/// charge hop takes one tick, delays are path lengths over charging edges.
+/// Feedback is broken at the back edges of a depth-first search from the
+/// input, so the longest delay is that of the longest acyclic path found.
+pub fn timing(module: &Module) -> Vec<PathDelay> {
+    let charging = charging_adjacency(module);
+    let reverse = reverse_adjacency(&charging);
+    let blocked = blocked_nodes(module);
+    let mut delays = vec![];
+    for (input, &from) in module.inputs.iter().enumerate() {
+        let shortest_from = shortest_paths(&charging, from);
+        let longest_from = longest_paths(&charging, from);
+        for (output, &to) in module.outputs.iter().enumerate() {
+            if let Some(shortest) = shortest_from[to] {
+                let backward = reachable(&reverse, to);
+                let gates = (0..charging.len())
+                    .filter(|&i| shortest_from[i].is_some() && backward[i] && blocked[i])
+                    .collect();
+                delays.push(PathDelay {
+                    input,
+                    output,
+                    shortest,
+                    longest: longest_from[to].unwrap_or(shortest),
+                    gates,
+                });
+            }
+        }
+    }
+    delays
+}
+
+fn charging_adjacency(module: &Module) -> Vec<Vec<usize>> {
+    module
+        .connections
+        .iter()
+        .map(|con| {
+            let mut targets: Vec<usize> = con.charging.iter().copied().collect();
+            targets.sort_unstable();
+            targets
+        })
+        .collect()
+}
+
+fn reverse_adjacency(adjacency: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut reverse = vec![vec![]; adjacency.len()];
+    for (from, targets) in adjacency.iter().enumerate() {
+        for &to in targets {
+            reverse[to].push(from);
+        }
+    }
+    reverse
+}
+
+fn blocked_nodes(module: &Module) -> Vec<bool> {
+    let mut blocked = vec![false; module.connections.len()];
+    for con in module.connections.iter() {
+        for &to in con.blocking.iter() {
+            blocked[to] = true;
+        }
+    }
+    blocked
+}
+
+fn reachable(adjacency: &[Vec<usize>], from: usize) -> Vec<bool> {
+    let mut seen = vec![false; adjacency.len()];
+    let mut stack = vec![from];
+    seen[from] = true;
+    while let Some(index) = stack.pop() {
+        for &next in adjacency[index].iter() {
+            if !seen[next] {
+                seen[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    seen
+}
+
+fn shortest_paths(adjacency: &[Vec<usize>], from: usize) -> Vec<Option<usize>> {
+    let mut distances = vec![None; adjacency.len()];
+    let mut queue = VecDeque::new();
+    distances[from] = Some(0);
+    queue.push_back(from);
+    while let Some(index) = queue.pop_front() {
+        let distance = distances[index].unwrap() + 1;
+        for &next in adjacency[index].iter() {
+            if distances[next].is_none() {
+                distances[next] = Some(distance);
+                queue.push_back(next);
+            }
+        }
+    }
+    distances
+}
+
+/// Longest path lengths from `from` once the back edges of a depth-first
+/// search are dropped, which leaves a DAG.
+fn longest_paths(adjacency: &[Vec<usize>], from: usize) -> Vec<Option<usize>> {
+    let mut on_stack = vec![false; adjacency.len()];
+    let mut visited = vec![false; adjacency.len()];
+    let mut postorder = vec![];
+    let mut dag = vec![vec![]; adjacency.len()];
+    let mut stack = vec![(from, 0)];
+    visited[from] = true;
+    on_stack[from] = true;
+    while let Some((index, cursor)) = stack.pop() {
+        match adjacency[index].get(cursor).copied() {
+            Some(next) => {
+                stack.push((index, cursor + 1));
+                if !on_stack[next] {
+                    dag[index].push(next);
+                }
+                if !visited[next] {
+                    visited[next] = true;
+                    on_stack[next] = true;
+                    stack.push((next, 0));
+                }
+            }
+            None => {
+                on_stack[index] = false;
+                postorder.push(index);
+            }
+        }
+    }
+    let mut distances = vec![None; adjacency.len()];
+    distances[from] = Some(0);
+    for &index in postorder.iter().rev() {
+        if let Some(distance) = distances[index] {
+            for &next in dag[index].iter() {
+                if distances[next].is_none_or(|d| d < distance + 1) {
+                    distances[next] = Some(distance + 1);
+                }
+            }
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        timing::{timing, PathDelay},
+        ModuleBuilder,
+    };
+
+    #[test]
+    fn chain_delay() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.input(0);
+        builder.output(2);
+        let delays = timing(&builder.build());
+        assert_eq!(
+            delays,
+            vec![PathDelay {
+                input: 0,
+                output: 0,
+                shortest: 2,
+                longest: 2,
+                gates: vec![]
+            }]
+        );
+        assert!(delays[0].is_balanced());
+    }
+
+    #[test]
+    fn unequal_paths() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(2, 3);
+        builder.charge(0, 3);
+        builder.block(4, 2);
+        builder.input(0);
+        builder.input(4);
+        builder.output(3);
+        let delays = timing(&builder.build());
+        assert_eq!(
+            delays,
+            vec![PathDelay {
+                input: 0,
+                output: 0,
+                shortest: 1,
+                longest: 3,
+                gates: vec![2]
+            }]
+        );
+        assert!(!delays[0].is_balanced());
+    }
+
+    #[test]
+    fn feedback_is_ignored() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(2, 1);
+        builder.charge(2, 3);
+        builder.input(0);
+        builder.output(3);
+        let delays = timing(&builder.build());
+        assert_eq!((delays[0].shortest, delays[0].longest), (3, 3));
+    }
+}
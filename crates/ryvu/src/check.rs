@@ -0,0 +1,445 @@
+use module::Module;
+use sat::{Lit, Solver};
+use std::{iter::Peekable, ops::Not, str::Chars};
+
+/// A condition over the outputs that must hold after every tick later than
+/// `after`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Property {
+    pub after: usize,
+    pub condition: Expr,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Expr {
+    Const(bool),
+    /// Position of an output in `Module::outputs`.
+    Output(usize),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PropertyError {
+    UnexpectedChar(usize, char),
+    UnexpectedEnd,
+    UnknownOutput(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Check {
+    /// No violation exists within the given number of ticks.
+    Holds(usize),
+    /// Input bits, one vector per tick, after which the property fails.
+    Violated(Vec<Vec<bool>>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bit {
+    Const(bool),
+    Var(Lit),
+}
+
+struct PropertyParser<'a> {
+    chars: Peekable<Chars<'a>>,
+    position: usize,
+    outputs: &'a [String],
+}
+
+struct Encoder {
+    solver: Solver,
+}
+
+/// Parses `[after <tick>] (always | never) <expr>`, where an expression is
+/// built from `$output` names, `0`, `1`, `!`, `&`, `|` and parentheses.
+pub fn parse_property(text: &str, outputs: &[String]) -> Result<Property, PropertyError> {
+    let mut parser = PropertyParser {
+        chars: text.chars().peekable(),
+        position: 0,
+        outputs,
+    };
+    let mut after = 0;
+    let mut word = parser.word()?;
+    if word == "after" {
+        after = parser.number()?;
+        word = parser.word()?;
+    }
+    let condition = match word.as_str() {
+        "always" => parser.expr()?,
+        "never" => Expr::Not(Box::new(parser.expr()?)),
+        _ => {
+            return Err(PropertyError::UnexpectedChar(
+                parser.position,
+                word_start(&word),
+            ))
+        }
+    };
+    parser.skip_space();
+    match parser.chars.peek() {
+        Some(&ch) => Err(PropertyError::UnexpectedChar(parser.position, ch)),
+        None => Ok(Property { after, condition }),
+    }
+}
+
+/// Unrolls the module over `bound` ticks from reset and searches for the
+/// shortest input trace that violates the property.
+pub fn check(module: &Module, property: &Property, bound: usize) -> Check {
+    let mut encoder = Encoder {
+        solver: Solver::default(),
+    };
+    let size = module.connections.len();
+    let mut charged = vec![Bit::Const(false); size];
+    let mut blocked = vec![Bit::Const(false); size];
+    let mut inputs: Vec<Vec<Lit>> = vec![];
+    for tick in 1..=bound {
+        let tick_inputs: Vec<Lit> = module
+            .inputs
+            .iter()
+            .map(|_| encoder.solver.new_var())
+            .collect();
+        for (position, &index) in module.inputs.iter().enumerate() {
+            charged[index] = encoder.or(&[charged[index], Bit::Var(tick_inputs[position])]);
+        }
+        inputs.push(tick_inputs);
+        let active: Vec<Bit> = (0..size)
+            .map(|index| encoder.and(&[charged[index], !blocked[index]]))
+            .collect();
+        let mut charging = vec![vec![]; size];
+        let mut blocking = vec![vec![]; size];
+        for (index, con) in module.connections.iter().enumerate() {
+            for &to in con.charging.iter() {
+                charging[to].push(active[index]);
+            }
+            for &to in con.blocking.iter() {
+                blocking[to].push(active[index]);
+            }
+        }
+        charged = charging.iter().map(|bits| encoder.or(bits)).collect();
+        blocked = blocking.iter().map(|bits| encoder.or(bits)).collect();
+        if tick <= property.after {
+            continue;
+        }
+        let outputs: Vec<Bit> = module.outputs.iter().map(|&i| charged[i]).collect();
+        let holds = encoder.expr(&property.condition, &outputs);
+        match holds {
+            Bit::Const(true) => {}
+            Bit::Const(false) => {
+                return Check::Violated(vec![vec![false; module.inputs.len()]; tick])
+            }
+            Bit::Var(lit) => {
+                if encoder.solver.solve_with(&[!lit]) {
+                    let trace = inputs
+                        .iter()
+                        .map(|tick| {
+                            tick.iter()
+                                .map(|&lit| encoder.solver.model_value(lit))
+                                .collect()
+                        })
+                        .collect();
+                    return Check::Violated(trace);
+                }
+                encoder.solver.add_clause(&[lit]);
+            }
+        }
+    }
+    Check::Holds(bound)
+}
+
+fn word_start(word: &str) -> char {
+    word.chars().next().unwrap_or(' ')
+}
+
+impl Property {
+    /// Whether the property holds for the outputs seen after `tick`.
+    pub fn holds(&self, tick: usize, outputs: &[bool]) -> bool {
+        tick <= self.after || self.condition.eval(outputs)
+    }
+}
+
+impl Expr {
+    pub fn eval(&self, outputs: &[bool]) -> bool {
+        match self {
+            Expr::Const(value) => *value,
+            Expr::Output(position) => outputs[*position],
+            Expr::Not(inner) => !inner.eval(outputs),
+            Expr::And(left, right) => left.eval(outputs) && right.eval(outputs),
+            Expr::Or(left, right) => left.eval(outputs) || right.eval(outputs),
+        }
+    }
+}
+
+impl<'a> PropertyParser<'a> {
+    fn skip_space(&mut self) {
+        while let Some(' ') = self.chars.peek() {
+            self.bump();
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        self.position += 1;
+        self.chars.next()
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_space();
+        self.chars.peek().copied()
+    }
+
+    fn word(&mut self) -> Result<String, PropertyError> {
+        self.skip_space();
+        let mut word = String::new();
+        while let Some(&ch) = self.chars.peek() {
+            if ch.is_ascii_alphanumeric() || ch == '_' {
+                word.push(ch);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        if word.is_empty() {
+            match self.chars.peek() {
+                Some(&ch) => Err(PropertyError::UnexpectedChar(self.position, ch)),
+                None => Err(PropertyError::UnexpectedEnd),
+            }
+        } else {
+            Ok(word)
+        }
+    }
+
+    fn number(&mut self) -> Result<usize, PropertyError> {
+        let start = self.position;
+        let word = self.word()?;
+        word.parse()
+            .map_err(|_| PropertyError::UnexpectedChar(start, word_start(&word)))
+    }
+
+    fn expr(&mut self) -> Result<Expr, PropertyError> {
+        let mut expr = self.conjunction()?;
+        while let Some('|') = self.peek() {
+            self.bump();
+            expr = Expr::Or(Box::new(expr), Box::new(self.conjunction()?));
+        }
+        Ok(expr)
+    }
+
+    fn conjunction(&mut self) -> Result<Expr, PropertyError> {
+        let mut expr = self.unary()?;
+        while let Some('&') = self.peek() {
+            self.bump();
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, PropertyError> {
+        match self.peek() {
+            Some('!') => {
+                self.bump();
+                Ok(Expr::Not(Box::new(self.unary()?)))
+            }
+            Some('(') => {
+                self.bump();
+                let expr = self.expr()?;
+                match self.peek() {
+                    Some(')') => {
+                        self.bump();
+                        Ok(expr)
+                    }
+                    Some(ch) => Err(PropertyError::UnexpectedChar(self.position, ch)),
+                    None => Err(PropertyError::UnexpectedEnd),
+                }
+            }
+            Some('0') => {
+                self.bump();
+                Ok(Expr::Const(false))
+            }
+            Some('1') => {
+                self.bump();
+                Ok(Expr::Const(true))
+            }
+            Some('$') => {
+                self.bump();
+                let name = self.word()?;
+                match self.outputs.iter().position(|output| output == &name) {
+                    Some(position) => Ok(Expr::Output(position)),
+                    None => Err(PropertyError::UnknownOutput(name)),
+                }
+            }
+            Some(ch) => Err(PropertyError::UnexpectedChar(self.position, ch)),
+            None => Err(PropertyError::UnexpectedEnd),
+        }
+    }
+}
+
+impl Not for Bit {
+    type Output = Bit;
+
+    fn not(self) -> Bit {
+        match self {
+            Bit::Const(value) => Bit::Const(!value),
+            Bit::Var(lit) => Bit::Var(!lit),
+        }
+    }
+}
+
+impl Encoder {
+    fn and(&mut self, bits: &[Bit]) -> Bit {
+        let inverted: Vec<Bit> = bits.iter().map(|&bit| !bit).collect();
+        !self.or(&inverted)
+    }
+
+    fn or(&mut self, bits: &[Bit]) -> Bit {
+        let mut lits = vec![];
+        for &bit in bits {
+            match bit {
+                Bit::Const(true) => return Bit::Const(true),
+                Bit::Const(false) => {}
+                Bit::Var(lit) => lits.push(lit),
+            }
+        }
+        match lits.len() {
+            0 => Bit::Const(false),
+            1 => Bit::Var(lits[0]),
+            _ => {
+                let out = self.solver.new_var();
+                let mut clause = lits.clone();
+                clause.push(!out);
+                self.solver.add_clause(&clause);
+                for lit in lits {
+                    self.solver.add_clause(&[!lit, out]);
+                }
+                Bit::Var(out)
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr, outputs: &[Bit]) -> Bit {
+        match expr {
+            Expr::Const(value) => Bit::Const(*value),
+            Expr::Output(position) => outputs[*position],
+            Expr::Not(inner) => !self.expr(inner, outputs),
+            Expr::And(left, right) => {
+                let bits = [self.expr(left, outputs), self.expr(right, outputs)];
+                self.and(&bits)
+            }
+            Expr::Or(left, right) => {
+                let bits = [self.expr(left, outputs), self.expr(right, outputs)];
+                self.or(&bits)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::check::{check, parse_property, Check, Expr, Property, PropertyError};
+    use module::{Module, ModuleBuilder};
+    use sim::network::Network;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|&name| name.to_owned()).collect()
+    }
+
+    /// `$a > $x` and `$a > m > $y`.
+    fn staggered() -> Module {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(0, 2);
+        builder.charge(2, 3);
+        builder.input(0);
+        builder.output(1);
+        builder.output(3);
+        builder.build()
+    }
+
+    fn replay(module: Module, trace: &[Vec<bool>]) -> Vec<bool> {
+        let mut network = Network::new(module);
+        network.replay(trace);
+        network.outputs()
+    }
+
+    #[test]
+    fn parse_properties() {
+        let outputs = names(&["ok", "a", "b"]);
+        assert_eq!(
+            parse_property("after 3 always $ok", &outputs),
+            Ok(Property {
+                after: 3,
+                condition: Expr::Output(0)
+            })
+        );
+        assert_eq!(
+            parse_property("never ($a & $b) | 0", &outputs),
+            Ok(Property {
+                after: 0,
+                condition: Expr::Not(Box::new(Expr::Or(
+                    Box::new(Expr::And(
+                        Box::new(Expr::Output(1)),
+                        Box::new(Expr::Output(2))
+                    )),
+                    Box::new(Expr::Const(false))
+                )))
+            })
+        );
+        assert_eq!(
+            parse_property("always $c", &outputs),
+            Err(PropertyError::UnknownOutput("c".to_owned()))
+        );
+        assert_eq!(
+            parse_property("always $a &", &outputs),
+            Err(PropertyError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn self_blocked_output_never_charges() {
+        // $a > $y, and $a both charges and blocks x, so x never reaches $z.
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(0, 2);
+        builder.block(0, 2);
+        builder.charge(2, 3);
+        builder.input(0);
+        builder.output(1);
+        builder.output(3);
+        let property = parse_property("never $y & $z", &names(&["y", "z"])).unwrap();
+        assert_eq!(check(&builder.build(), &property, 10), Check::Holds(10));
+    }
+
+    #[test]
+    fn violation_trace_replays() {
+        let property = parse_property("never $x & $y", &names(&["x", "y"])).unwrap();
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(3, 4);
+        builder.input(0);
+        builder.input(3);
+        builder.output(2);
+        builder.output(4);
+        let module = builder.build();
+        match check(&module, &property, 10) {
+            Check::Violated(trace) => {
+                assert_eq!(trace.len(), 2);
+                assert_eq!(replay(module, &trace), vec![true, true]);
+            }
+            Check::Holds(_) => panic!("expected a violation"),
+        }
+    }
+
+    #[test]
+    fn after_skips_early_ticks() {
+        let outputs = names(&["x", "y"]);
+        let property = parse_property("after 1 always !$y", &outputs).unwrap();
+        match check(&staggered(), &property, 10) {
+            Check::Violated(trace) => assert_eq!(trace, vec![vec![true], vec![false]]),
+            Check::Holds(_) => panic!("expected a violation"),
+        }
+        let property = parse_property("after 1 always !$x", &outputs).unwrap();
+        assert!(matches!(
+            check(&staggered(), &property, 10),
+            Check::Violated(_)
+        ));
+    }
+}
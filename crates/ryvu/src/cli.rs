@@ -0,0 +1,232 @@
+//! The commands behind the `ryvu` binary. Each one takes the arguments
+//! following its name and returns what to print, so `main` only dispatches
+//! and prints.
+
+pub mod analysis;
+pub mod export;
+pub mod simulate;
+pub mod source;
+pub mod verify;
+
+use crate::check::{parse_property, Property, PropertyError};
+use compile::{compile, CompilationResult, SourceMap};
+use module::Module;
+use std::{fmt::Write, fs, path::Path};
+
+const TICK_CAP: usize = 100_000;
+
+/// What a command prints.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// For standard output.
+    pub text: String,
+    /// For standard error, next to output meant to be piped elsewhere.
+    pub notes: String,
+    /// The command found a failure, like a violated property.
+    pub failed: bool,
+}
+
+impl Report {
+    fn failure(text: String) -> Report {
+        Report {
+            text,
+            failed: true,
+            ..Report::default()
+        }
+    }
+}
+
+impl From<String> for Report {
+    fn from(text: String) -> Report {
+        Report {
+            text,
+            ..Report::default()
+        }
+    }
+}
+
+/// A command that could not run.
+#[derive(Debug, PartialEq, Eq)]
+pub enum CliError {
+    /// The arguments do not match the usage line held.
+    Usage(&'static str),
+    /// Anything else, as the message to print.
+    Message(String),
+}
+
+/// The names of a compiled file.
+pub struct Symbols {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    pub nodes: Vec<String>,
+    pub source_map: SourceMap,
+}
+
+/// Compiles `source`, or returns its lexer and parser errors.
+pub fn compile_source(source: &str) -> Result<(Module, Symbols), CliError> {
+    let cr = compile(source, true, true);
+    match cr.module {
+        Some(module) => {
+            let symbols = Symbols {
+                inputs: cr.input_ids.unwrap_or_default(),
+                outputs: cr.output_ids.unwrap_or_default(),
+                nodes: cr.node_ids.unwrap_or_default(),
+                source_map: cr.source_map.unwrap_or_default(),
+            };
+            Ok((module, symbols))
+        }
+        None => Err(CliError::Message(compile_errors(&cr))),
+    }
+}
+
+/// One line per lexer or parser error of a failed compilation.
+pub(crate) fn compile_errors(cr: &CompilationResult) -> String {
+    let lines: Vec<String> = cr
+        .lerrors
+        .iter()
+        .map(|err| format!("{:?}", err))
+        .chain(cr.perrors.iter().map(|err| format!("{:?}", err)))
+        .collect();
+    lines.join("\n")
+}
+
+pub(crate) fn load_module(path: &str) -> Result<(Module, Symbols), CliError> {
+    compile_source(&read_file(path)?)
+}
+
+pub(crate) fn read_file(path: &str) -> Result<String, CliError> {
+    fs::read_to_string(path)
+        .map_err(|_| CliError::Message(format!("could not open file '{}'", path)))
+}
+
+pub(crate) fn write_file(path: &Path, content: String) -> Result<(), CliError> {
+    fs::write(path, content)
+        .map_err(|_| CliError::Message(format!("could not write file '{}'", path.display())))
+}
+
+pub(crate) fn load_property(text: &str, symbols: &Symbols) -> Result<Property, CliError> {
+    parse_property(text, &symbols.outputs).map_err(|err| CliError::Message(property_error(err)))
+}
+
+pub(crate) fn property_error(err: PropertyError) -> String {
+    match err {
+        PropertyError::UnexpectedChar(position, ch) => {
+            format!("unexpected '{}' at {} in property", ch, position)
+        }
+        PropertyError::UnexpectedEnd => "unexpected end of property".to_owned(),
+        PropertyError::UnknownOutput(name) => format!("unknown output '${}' in property", name),
+    }
+}
+
+/// Removes `name` and the argument following it, returning the latter.
+pub(crate) fn take_option(args: &mut Vec<String>, name: &str) -> Result<Option<String>, CliError> {
+    let position = match args.iter().position(|arg| arg == name) {
+        Some(position) => position,
+        None => return Ok(None),
+    };
+    if position + 1 >= args.len() {
+        return Err(CliError::Message(format!("missing value for {}", name)));
+    }
+    args.remove(position);
+    Ok(Some(args.remove(position)))
+}
+
+/// Reads one line of input bits per tick, with blank lines separating
+/// vectors and `#` starting a comment.
+pub(crate) fn read_vectors(
+    path: &str,
+    input_count: usize,
+) -> Result<Vec<Vec<Vec<bool>>>, CliError> {
+    parse_vectors(&read_file(path)?, input_count)
+}
+
+fn parse_vectors(text: &str, input_count: usize) -> Result<Vec<Vec<Vec<bool>>>, CliError> {
+    let mut vectors = vec![vec![]];
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            if !vectors.last().unwrap().is_empty() {
+                vectors.push(vec![]);
+            }
+        } else {
+            vectors
+                .last_mut()
+                .unwrap()
+                .push(parse_bits(line, input_count)?);
+        }
+    }
+    if vectors.last().unwrap().is_empty() {
+        vectors.pop();
+    }
+    Ok(vectors)
+}
+
+pub(crate) fn parse_bits(text: &str, count: usize) -> Result<Vec<bool>, CliError> {
+    let bits: Vec<bool> = text.chars().map(|ch| ch == '1').collect();
+    if bits.len() != count || text.chars().any(|ch| ch != '0' && ch != '1') {
+        return Err(CliError::Message(format!(
+            "expected {} input bits, found '{}'",
+            count, text
+        )));
+    }
+    Ok(bits)
+}
+
+pub(crate) fn parse_number(text: &str) -> Result<usize, CliError> {
+    text.parse()
+        .map_err(|_| CliError::Message(format!("invalid number '{}'", text)))
+}
+
+pub(crate) fn parse_tick_cap(text: Option<&String>) -> Result<usize, CliError> {
+    match text {
+        Some(cap) => parse_number(cap),
+        None => Ok(TICK_CAP),
+    }
+}
+
+pub(crate) fn bit_string(bits: &[bool]) -> String {
+    bits.iter()
+        .map(|&bit| if bit { '1' } else { '0' })
+        .collect()
+}
+
+/// One line of input bits per tick.
+pub(crate) fn write_trace(text: &mut String, trace: &[Vec<bool>]) {
+    for bits in trace {
+        let _ = writeln!(text, "{}", bit_string(bits));
+    }
+}
+
+pub(crate) fn node_names<'a>(symbols: &'a Symbols, nodes: &[usize]) -> Vec<&'a str> {
+    nodes
+        .iter()
+        .map(|&index| symbols.nodes[index].as_str())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::{parse_vectors, take_option, CliError};
+
+    #[test]
+    fn vectors_and_options() {
+        let text = "01 # first\n11\n\n\n10\n";
+        assert_eq!(
+            parse_vectors(text, 2),
+            Ok(vec![
+                vec![vec![false, true], vec![true, true]],
+                vec![vec![true, false]]
+            ])
+        );
+        assert_eq!(
+            parse_vectors("012", 3),
+            Err(CliError::Message(
+                "expected 3 input bits, found '012'".to_owned()
+            ))
+        );
+        let mut args: Vec<String> = vec!["a".into(), "--seed".into(), "3".into(), "b".into()];
+        assert_eq!(take_option(&mut args, "--seed"), Ok(Some("3".to_owned())));
+        assert_eq!(args, vec!["a".to_owned(), "b".to_owned()]);
+        assert_eq!(take_option(&mut args, "--runs"), Ok(None));
+    }
+}
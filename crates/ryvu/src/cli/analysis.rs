@@ -0,0 +1,177 @@
+use crate::{
+    cli::{load_module, node_names, parse_number, CliError, Report, Symbols},
+    equivalence::MAX_INPUTS,
+    loops::{feedback_loops, LoopKind},
+    reach::{explore, TooManyInputs},
+};
+use module::{stats::stats, timing::timing, Module};
+use std::fmt::Write;
+
+const STATE_CAP: usize = 100_000;
+
+/// `ryvu timing <path>`
+pub fn timing_command(args: &[String]) -> Result<Report, CliError> {
+    if args.is_empty() {
+        return Err(CliError::Usage("ryvu timing <path>"));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    let mut text = String::new();
+    for delay in timing(&module) {
+        let _ = write!(
+            text,
+            "${} -> ${}: ",
+            symbols.inputs[delay.input], symbols.outputs[delay.output]
+        );
+        if delay.is_balanced() {
+            let _ = write!(text, "{} ticks", delay.shortest);
+        } else {
+            let _ = write!(
+                text,
+                "{}..{} ticks, unbalanced",
+                delay.shortest, delay.longest
+            );
+        }
+        if !delay.gates.is_empty() {
+            let _ = write!(
+                text,
+                ", gated by {}",
+                node_names(&symbols, &delay.gates).join(", ")
+            );
+        }
+        text.push('\n');
+    }
+    Ok(text.into())
+}
+
+/// `ryvu stats <path>`
+pub fn stats_command(args: &[String]) -> Result<Report, CliError> {
+    if args.is_empty() {
+        return Err(CliError::Usage("ryvu stats <path>"));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    Ok(stats_text(&module, &symbols).into())
+}
+
+fn stats_text(module: &Module, symbols: &Symbols) -> String {
+    let stats = stats(module);
+    let mut text = String::new();
+    let _ = writeln!(
+        text,
+        "nodes: {} ({} inputs, {} outputs)",
+        stats.nodes, stats.inputs, stats.outputs
+    );
+    let _ = writeln!(
+        text,
+        "edges: {} charging, {} blocking",
+        stats.charging_edges, stats.blocking_edges
+    );
+    let _ = writeln!(text, "fan-in: {}", distribution(&stats.fan_in));
+    let _ = writeln!(text, "fan-out: {}", distribution(&stats.fan_out));
+    if !stats.max_fan_out.is_empty() {
+        let _ = writeln!(
+            text,
+            "max fan-out: {} ({})",
+            stats.fan_out.len() - 1,
+            node_names(symbols, &stats.max_fan_out).join(", ")
+        );
+    }
+    let _ = writeln!(text, "loops: {}", stats.loops);
+    let _ = writeln!(text, "self-loops: {}", stats.self_loops);
+    text
+}
+
+/// Formats node counts by degree as `degree:count`, skipping empty degrees.
+fn distribution(counts: &[usize]) -> String {
+    let entries: Vec<String> = counts
+        .iter()
+        .enumerate()
+        .filter(|(_, &count)| count > 0)
+        .map(|(degree, count)| format!("{}:{}", degree, count))
+        .collect();
+    entries.join(" ")
+}
+
+/// `ryvu loops <path>`
+pub fn loops_command(args: &[String]) -> Result<Report, CliError> {
+    if args.is_empty() {
+        return Err(CliError::Usage("ryvu loops <path>"));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    let mut text = String::new();
+    for feedback in feedback_loops(&module) {
+        let kind = match feedback.kind {
+            LoopKind::ChargeRing => "charge ring",
+            LoopKind::Latch => "latch",
+            LoopKind::SelfBlocking => "self-blocking",
+        };
+        let _ = writeln!(
+            text,
+            "{}: {}",
+            kind,
+            node_names(&symbols, &feedback.nodes).join(", ")
+        );
+    }
+    Ok(text.into())
+}
+
+/// `ryvu reach <path> [--dot] [state cap]`
+pub fn reach_command(args: &[String]) -> Result<Report, CliError> {
+    let dot = args.iter().any(|arg| arg == "--dot");
+    let args: Vec<&String> = args.iter().filter(|&arg| arg != "--dot").collect();
+    if args.is_empty() {
+        return Err(CliError::Usage("ryvu reach <path> [--dot] [state cap]"));
+    }
+    let (module, symbols) = load_module(args[0])?;
+    let max_states = match args.get(1) {
+        Some(cap) => parse_number(cap)?,
+        None => STATE_CAP,
+    };
+    let graph = explore(module, max_states).map_err(|TooManyInputs(count)| {
+        CliError::Message(format!(
+            "too many inputs to enumerate: {}, at most {}",
+            count, MAX_INPUTS
+        ))
+    })?;
+    if dot {
+        return Ok(graph.to_dot(&symbols.nodes).into());
+    }
+    let mut text = String::new();
+    let _ = writeln!(
+        text,
+        "{} states, {} transitions",
+        graph.states.len(),
+        graph.transitions.len()
+    );
+    if !graph.complete {
+        let _ = writeln!(text, "stopped at {} states", max_states);
+    }
+    for sink in graph.sinks() {
+        let state = &graph.states[sink];
+        let charged: Vec<&str> = (0..symbols.nodes.len())
+            .filter(|&index| state.is_charged(index))
+            .map(|index| symbols.nodes[index].as_str())
+            .collect();
+        let _ = writeln!(text, "sink state {}: {}", sink, charged.join(", "));
+    }
+    Ok(text.into())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::{analysis::stats_text, compile_source};
+
+    #[test]
+    fn stats_report() {
+        let (module, symbols) = compile_source("$a > b, c\nb . c\nc > c > $o").unwrap();
+        assert_eq!(
+            stats_text(&module, &symbols),
+            "nodes: 4 (1 inputs, 1 outputs)\n\
+             edges: 4 charging, 1 blocking\n\
+             fan-in: 0:1 1:2 3:1\n\
+             fan-out: 0:1 1:1 2:2\n\
+             max fan-out: 2 (a, c)\n\
+             loops: 1\n\
+             self-loops: 1\n"
+        );
+    }
+}
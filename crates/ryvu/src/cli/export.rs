@@ -0,0 +1,63 @@
+use crate::cli::{load_module, read_vectors, take_option, write_file, CliError, Report};
+use codegen::{
+    c::c,
+    rust::rust,
+    verilog::{testbench, verilog},
+};
+use sim::network::Network;
+use std::path::Path;
+
+/// `ryvu rust <path> [type name]`
+pub fn rust_command(args: &[String]) -> Result<Report, CliError> {
+    if args.is_empty() || args.len() > 2 {
+        return Err(CliError::Usage("ryvu rust <path> [type name]"));
+    }
+    let (module, _) = load_module(&args[0])?;
+    let type_name = args.get(1).map(String::as_str).unwrap_or("Circuit");
+    Ok(rust(&module, type_name).into())
+}
+
+/// `ryvu c <path> <prefix> [output directory]`, writing `<prefix>.h` and
+/// `<prefix>.c`.
+pub fn c_command(args: &[String]) -> Result<Report, CliError> {
+    if args.len() < 2 || args.len() > 3 {
+        return Err(CliError::Usage("ryvu c <path> <prefix> [output directory]"));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    let prefix = &args[1];
+    let directory = Path::new(args.get(2).map(String::as_str).unwrap_or("."));
+    let files = c(&module, &symbols.inputs, &symbols.outputs, prefix);
+    for (extension, content) in [("h", files.header), ("c", files.source)] {
+        let path = directory.join(format!("{}.{}", prefix, extension));
+        write_file(&path, content)?;
+    }
+    Ok(Report::default())
+}
+
+/// `ryvu verilog <path> <name> [output directory] [--testbench <vectors
+/// path>]`, writing `<name>.v` and, with vectors, `<name>_tb.v`.
+pub fn verilog_command(args: &[String]) -> Result<Report, CliError> {
+    let mut args = args.to_vec();
+    let vectors_path = take_option(&mut args, "--testbench")?;
+    if args.len() < 2 || args.len() > 3 {
+        return Err(CliError::Usage(
+            "ryvu verilog <path> <name> [output directory] [--testbench <vectors path>]",
+        ));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    let name = &args[1];
+    let directory = Path::new(args.get(2).map(String::as_str).unwrap_or("."));
+    let code = verilog(&module, &symbols.inputs, &symbols.outputs, name);
+    write_file(&directory.join(format!("{}.v", name)), code)?;
+    if let Some(vectors_path) = vectors_path {
+        let vectors = read_vectors(&vectors_path, module.inputs.len())?;
+        let mut network = Network::new(module);
+        let expected: Vec<Vec<Vec<bool>>> = vectors
+            .iter()
+            .map(|vector| network.replay(vector))
+            .collect();
+        let code = testbench(&symbols.inputs, &symbols.outputs, name, &vectors, &expected);
+        write_file(&directory.join(format!("{}_tb.v", name)), code)?;
+    }
+    Ok(Report::default())
+}
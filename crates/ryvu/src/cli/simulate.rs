@@ -0,0 +1,220 @@
+use crate::{
+    cli::{
+        bit_string, load_module, parse_bits, parse_number, parse_tick_cap, read_vectors,
+        take_option, CliError, Report,
+    },
+    glitch::glitches,
+};
+use sim::{
+    batch::run_batch,
+    history::History,
+    network::Network,
+    settle::{settle, Settling},
+};
+use std::{
+    fmt::Write,
+    io::{BufReader, Read},
+    sync::Arc,
+    thread,
+};
+
+const HISTORY_DEPTH: usize = 4096;
+
+/// `ryvu <path>`: reads input bits from `input` and ticks once all inputs
+/// are given, handing the outputs to `show`. `b` steps back a tick, or as
+/// many as the number right after it, as in `b12`, keeping the bits already
+/// typed for the next tick. `q` or the end of `input` quits.
+pub fn interactive_command(
+    args: &[String],
+    input: impl Read,
+    show: impl FnMut(&str),
+) -> Result<(), CliError> {
+    let (module, _) = load_module(&args[0])?;
+    interact(
+        History::new(Network::new(module), HISTORY_DEPTH),
+        input,
+        show,
+    );
+    Ok(())
+}
+
+fn interact(mut history: History, input: impl Read, mut show: impl FnMut(&str)) {
+    let input_count = history.network().module().inputs.len();
+    let mut bytes = BufReader::new(input).bytes().peekable();
+    let mut input_data = vec![];
+    loop {
+        if input_data.len() == input_count {
+            history.set_inputs(&input_data);
+            history.next();
+            input_data.clear();
+            show(&bit_string(&history.network().outputs()));
+            continue;
+        }
+        match bytes.next() {
+            Some(Ok(b'0')) => input_data.push(false),
+            Some(Ok(b'1')) => input_data.push(true),
+            Some(Ok(b'b')) => {
+                let mut ticks = None;
+                while let Some(&Ok(digit @ b'0'..=b'9')) = bytes.peek() {
+                    let digit = (digit - b'0') as usize;
+                    ticks = Some(
+                        ticks
+                            .unwrap_or(0usize)
+                            .saturating_mul(10)
+                            .saturating_add(digit),
+                    );
+                    bytes.next();
+                }
+                history.back(ticks.unwrap_or(1));
+                show(&bit_string(&history.network().outputs()));
+            }
+            Some(Ok(b'q')) | Some(Err(_)) | None => return,
+            Some(Ok(_)) => {}
+        }
+    }
+}
+
+/// `ryvu settle <path> <input bits> [tick cap]`
+pub fn settle_command(args: &[String]) -> Result<Report, CliError> {
+    if args.len() < 2 {
+        return Err(CliError::Usage(
+            "ryvu settle <path> <input bits> [tick cap]",
+        ));
+    }
+    let (module, _) = load_module(&args[0])?;
+    let input_data = parse_bits(&args[1], module.inputs.len())?;
+    let max_ticks = parse_tick_cap(args.get(2))?;
+    let mut network = Network::new(module);
+    Ok(match settle(&mut network, &input_data, max_ticks) {
+        Some(Settling {
+            transient,
+            period: 1,
+        }) => format!(
+            "stable after {} ticks: {}\n",
+            transient,
+            bit_string(&network.outputs())
+        )
+        .into(),
+        Some(Settling { transient, period }) => format!(
+            "oscillates with period {} after {} ticks\n",
+            period, transient
+        )
+        .into(),
+        None => Report::failure(format!("no repeated state within {} ticks\n", max_ticks)),
+    })
+}
+
+/// `ryvu glitch <path> <input bits before> <input bits after> [tick cap]`
+pub fn glitch_command(args: &[String]) -> Result<Report, CliError> {
+    if args.len() < 3 {
+        return Err(CliError::Usage(
+            "ryvu glitch <path> <input bits before> <input bits after> [tick cap]",
+        ));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    let before = parse_bits(&args[1], module.inputs.len())?;
+    let after = parse_bits(&args[2], module.inputs.len())?;
+    let max_ticks = parse_tick_cap(args.get(3))?;
+    let mut network = Network::new(module);
+    let report = match glitches(&mut network, &before, &after, max_ticks) {
+        Some(report) => report,
+        None => {
+            return Ok(Report::failure(format!(
+                "no repeated state within {} ticks\n",
+                max_ticks
+            )))
+        }
+    };
+    let mut text = String::new();
+    for glitch in report.glitches.iter() {
+        let _ = writeln!(
+            text,
+            "${} toggled {} times:",
+            symbols.outputs[glitch.output],
+            glitch.toggles.len()
+        );
+        for toggle in glitch.toggles.iter() {
+            let drivers: Vec<String> = toggle
+                .drivers
+                .iter()
+                .map(|&(from, to, is_charge)| {
+                    let sign = if is_charge { ">" } else { "." };
+                    format!("{} {} {}", symbols.nodes[from], sign, symbols.nodes[to])
+                })
+                .collect();
+            let _ = writeln!(
+                text,
+                "  tick {}: {} (by {})",
+                toggle.tick,
+                if toggle.value { 1 } else { 0 },
+                drivers.join(", ")
+            );
+        }
+    }
+    if report.settling.is_stable() {
+        let _ = writeln!(text, "stable after {} ticks", report.settling.transient);
+    } else {
+        let _ = writeln!(
+            text,
+            "oscillates with period {} after {} ticks",
+            report.settling.period, report.settling.transient
+        );
+    }
+    Ok(text.into())
+}
+
+/// `ryvu batch <path> <vectors path> [--threads n]`
+pub fn batch_command(args: &[String]) -> Result<Report, CliError> {
+    let mut args = args.to_vec();
+    let threads = match take_option(&mut args, "--threads")? {
+        Some(threads) => parse_number(&threads)?,
+        None => thread::available_parallelism().map_or(1, usize::from),
+    };
+    if args.len() != 2 {
+        return Err(CliError::Usage(
+            "ryvu batch <path> <vectors path> [--threads n]",
+        ));
+    }
+    let (module, _) = load_module(&args[0])?;
+    let vectors = read_vectors(&args[1], module.inputs.len())?;
+    let results = run_batch(&Arc::new(module), &vectors, threads);
+    let blocks: Vec<String> = results
+        .into_iter()
+        .map(|outputs| {
+            outputs
+                .iter()
+                .map(|output_data| bit_string(output_data) + "\n")
+                .collect()
+        })
+        .collect();
+    Ok(blocks.join("\n").into())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::{compile_source, simulate::interact};
+    use sim::{history::History, network::Network};
+
+    fn session(source: &str, input: &str) -> Vec<String> {
+        let (module, _) = compile_source(source).unwrap();
+        let mut shown = vec![];
+        interact(
+            History::new(Network::new(module), 16),
+            input.as_bytes(),
+            |outputs| shown.push(outputs.to_owned()),
+        );
+        shown
+    }
+
+    #[test]
+    fn back_honours_count() {
+        let shown = session("$a > b > c > $o", "1\n0\n0\n0\nb3\n0\n0\nb\nq1");
+        assert_eq!(shown, vec!["0", "0", "1", "0", "0", "0", "1", "0"]);
+    }
+
+    #[test]
+    fn back_keeps_pending_input() {
+        let shown = session("$a > b > $o\n$c > b", "10\n00\n1b1\n0\n");
+        assert_eq!(shown, vec!["0", "1", "0", "1"]);
+    }
+}
@@ -0,0 +1,45 @@
+use crate::cli::{read_file, write_file, CliError, Report};
+use compile::{
+    blif::import_blif,
+    rename::{rename, RenameError},
+};
+use std::{fmt::Write, path::Path};
+
+/// `ryvu blif <path>`: the BLIF file as ryvu source, with the delay and
+/// power input as notes.
+pub fn blif_command(args: &[String]) -> Result<Report, CliError> {
+    if args.len() != 1 {
+        return Err(CliError::Usage("ryvu blif <path>"));
+    }
+    let import = import_blif(&read_file(&args[0])?)
+        .map_err(|err| CliError::Message(format!("{:?}", err)))?;
+    let mut notes = format!("delay: {} ticks\n", import.delay);
+    if let Some(power) = import.power {
+        let _ = writeln!(notes, "charge ${} on the first tick", import.inputs[power]);
+    }
+    Ok(Report {
+        text: import.source(),
+        notes,
+        failed: false,
+    })
+}
+
+/// `ryvu rename <path> <old name> <new name>`: renames a node in place.
+pub fn rename_command(args: &[String]) -> Result<Report, CliError> {
+    if args.len() != 3 {
+        return Err(CliError::Usage("ryvu rename <path> <old name> <new name>"));
+    }
+    let source = rename(&read_file(&args[0])?, &args[1], &args[2]).map_err(|err| {
+        CliError::Message(match err {
+            RenameError::Lexer(errors) => {
+                let lines: Vec<String> = errors.iter().map(|err| format!("{:?}", err)).collect();
+                lines.join("\n")
+            }
+            RenameError::InvalidName(name) => format!("'{}' is not a valid identifier", name),
+            RenameError::Undefined(name) => format!("no node named '{}'", name),
+            RenameError::Collision(name) => format!("a node named '{}' already exists", name),
+        })
+    })?;
+    write_file(Path::new(&args[0]), source)?;
+    Ok(Report::default())
+}
@@ -0,0 +1,313 @@
+use crate::{
+    check::{check, parse_property, Check},
+    cli::{
+        compile_errors, compile_source, load_module, load_property, parse_number, parse_tick_cap,
+        property_error, read_file, read_vectors, take_option, write_file, write_trace, CliError,
+        Report, Symbols,
+    },
+    coverage::CoverageReport,
+    equivalence::{equivalence, Equivalence, EquivalenceError, MAX_INPUTS},
+    fault::{faults, simulate_faults, Fault},
+    fuzz::{fuzz, FuzzConfig, Reference},
+};
+use compile::{CompilationResult, IncrementalCompiler};
+use module::Module;
+use sim::network::Network;
+use std::{
+    convert::Infallible,
+    fmt::Write,
+    fs,
+    path::Path,
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const CHECK_BOUND: usize = 32;
+const FUZZ_RUNS: usize = 1000;
+const FUZZ_TICKS: usize = 64;
+const WATCH_INTERVAL: Duration = Duration::from_millis(200);
+
+/// `ryvu equiv <path> <path> [tick cap]`
+pub fn equiv_command(args: &[String]) -> Result<Report, CliError> {
+    if args.len() < 2 {
+        return Err(CliError::Usage("ryvu equiv <path> <path> [tick cap]"));
+    }
+    let (left, _) = load_module(&args[0])?;
+    let (right, _) = load_module(&args[1])?;
+    let max_ticks = parse_tick_cap(args.get(2))?;
+    match equivalence(left, right, max_ticks) {
+        Ok(Equivalence::Proven) => Ok("equivalent\n".to_owned().into()),
+        Ok(Equivalence::Bounded(ticks)) => Ok(format!("equivalent for {} ticks\n", ticks).into()),
+        Ok(Equivalence::Counterexample(trace)) => {
+            let mut text = "outputs differ after input trace:\n".to_owned();
+            write_trace(&mut text, &trace);
+            Ok(Report::failure(text))
+        }
+        Err(EquivalenceError::InputCount(left, right)) => Err(CliError::Message(format!(
+            "input counts differ: {} and {}",
+            left, right
+        ))),
+        Err(EquivalenceError::OutputCount(left, right)) => Err(CliError::Message(format!(
+            "output counts differ: {} and {}",
+            left, right
+        ))),
+        Err(EquivalenceError::TooManyInputs(count)) => Err(CliError::Message(format!(
+            "too many inputs to enumerate: {}, at most {}",
+            count, MAX_INPUTS
+        ))),
+    }
+}
+
+/// `ryvu check <path> <property> [bound]`
+pub fn check_command(args: &[String]) -> Result<Report, CliError> {
+    if args.len() < 2 {
+        return Err(CliError::Usage("ryvu check <path> <property> [bound]"));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    let property = load_property(&args[1], &symbols)?;
+    let bound = match args.get(2) {
+        Some(bound) => parse_number(bound)?,
+        None => CHECK_BOUND,
+    };
+    Ok(match check(&module, &property, bound) {
+        Check::Holds(ticks) => format!("holds for {} ticks\n", ticks).into(),
+        Check::Violated(trace) => {
+            let mut text = format!("violated at tick {} after input trace:\n", trace.len());
+            write_trace(&mut text, &trace);
+            Report::failure(text)
+        }
+    })
+}
+
+/// `ryvu fuzz <path> (<property> | --ref <path>) [--seed n] [--runs n]
+/// [--ticks n]`
+pub fn fuzz_command(args: &[String]) -> Result<Report, CliError> {
+    let mut args = args.to_vec();
+    let reference = take_option(&mut args, "--ref")?;
+    let config = FuzzConfig {
+        seed: match take_option(&mut args, "--seed")? {
+            Some(seed) => parse_number(&seed)? as u64,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(1, |time| time.as_nanos() as u64),
+        },
+        runs: match take_option(&mut args, "--runs")? {
+            Some(runs) => parse_number(&runs)?,
+            None => FUZZ_RUNS,
+        },
+        ticks: match take_option(&mut args, "--ticks")? {
+            Some(ticks) => parse_number(&ticks)?,
+            None => FUZZ_TICKS,
+        },
+    };
+    if args.is_empty() || (reference.is_none() == (args.len() < 2)) {
+        return Err(CliError::Usage(
+            "ryvu fuzz <path> (<property> | --ref <path>) [--seed n] [--runs n] [--ticks n]",
+        ));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    let trace = match reference {
+        Some(path) => {
+            let (reference, _) = load_module(&path)?;
+            if reference.inputs.len() != module.inputs.len()
+                || reference.outputs.len() != module.outputs.len()
+            {
+                return Err(CliError::Message(
+                    "port counts differ from the reference".to_owned(),
+                ));
+            }
+            fuzz(module, &mut Reference::new(reference), &config)
+        }
+        None => fuzz(module, &mut load_property(&args[1], &symbols)?, &config),
+    };
+    Ok(match trace {
+        None => format!(
+            "no failure in {} runs (seed {})\n",
+            config.runs, config.seed
+        )
+        .into(),
+        Some(trace) => {
+            let mut text = format!("failure (seed {}) after input trace:\n", config.seed);
+            write_trace(&mut text, &trace);
+            Report::failure(text)
+        }
+    })
+}
+
+/// `ryvu cover <path> <vectors path> [--html <output path>]`
+pub fn cover_command(args: &[String]) -> Result<Report, CliError> {
+    let mut args = args.to_vec();
+    let html = take_option(&mut args, "--html")?;
+    if args.len() < 2 {
+        return Err(CliError::Usage(
+            "ryvu cover <path> <vectors path> [--html <output path>]",
+        ));
+    }
+    let source = read_file(&args[0])?;
+    let (module, symbols) = compile_source(&source)?;
+    let vectors = read_vectors(&args[1], module.inputs.len())?;
+    let mut network = Network::new(module);
+    network.enable_coverage();
+    for vector in vectors {
+        network.reset();
+        for input_data in vector {
+            network.set_inputs(&input_data);
+            network.next();
+        }
+    }
+    let report = CoverageReport {
+        module: network.module(),
+        coverage: network.coverage().unwrap(),
+        names: &symbols.nodes,
+        source: &source,
+        source_map: &symbols.source_map,
+    };
+    match html {
+        Some(path) => {
+            write_file(Path::new(&path), report.html())?;
+            Ok(Report::default())
+        }
+        None => Ok(report.text().into()),
+    }
+}
+
+/// `ryvu faults <path> <vectors path> [--flip <tick>]`
+pub fn faults_command(args: &[String]) -> Result<Report, CliError> {
+    let mut args = args.to_vec();
+    let flip = match take_option(&mut args, "--flip")? {
+        Some(tick) => Some(parse_number(&tick)?),
+        None => None,
+    };
+    if flip == Some(0) {
+        return Err(CliError::Message("ticks are counted from 1".to_owned()));
+    }
+    if args.len() < 2 {
+        return Err(CliError::Usage(
+            "ryvu faults <path> <vectors path> [--flip <tick>]",
+        ));
+    }
+    let (module, symbols) = load_module(&args[0])?;
+    let vectors = read_vectors(&args[1], module.inputs.len())?;
+    Ok(fault_text(&module, &symbols, &vectors, flip).into())
+}
+
+fn fault_text(
+    module: &Module,
+    symbols: &Symbols,
+    vectors: &[Vec<Vec<bool>>],
+    flip: Option<usize>,
+) -> String {
+    let mut fault_list = faults(module);
+    if let Some(tick) = flip {
+        fault_list.extend((0..module.connections.len()).map(|node| Fault::Flip { node, tick }));
+    }
+    let report = simulate_faults(module, &fault_list, vectors);
+    let name = |index: usize| symbols.nodes[index].as_str();
+    let mut text = String::new();
+    for (fault, detected_by) in report.faults.iter().zip(report.detected_by.iter()) {
+        let description = match *fault {
+            Fault::StuckCharged(node) => format!("{} stuck at 1", name(node)),
+            Fault::StuckDischarged(node) => format!("{} stuck at 0", name(node)),
+            Fault::DropCharge(from, to) => format!("drop {} > {}", name(from), name(to)),
+            Fault::DropBlock(from, to) => format!("drop {} . {}", name(from), name(to)),
+            Fault::Flip { node, tick } => format!("{} flipped at tick {}", name(node), tick),
+        };
+        if detected_by.is_empty() {
+            let _ = writeln!(text, "{}: undetected", description);
+        } else {
+            let vectors: Vec<String> = detected_by.iter().map(|v| (v + 1).to_string()).collect();
+            let _ = writeln!(
+                text,
+                "{}: detected by vectors {}",
+                description,
+                vectors.join(", ")
+            );
+        }
+    }
+    let _ = writeln!(
+        text,
+        "fault coverage: {}/{}",
+        report.detected(),
+        report.faults.len()
+    );
+    text
+}
+
+/// `ryvu watch <path> [property]... [--bound n]`: recompiles the file
+/// whenever it changes and checks every property against the new module,
+/// handing each report to `show`. Runs until interrupted.
+pub fn watch_command(
+    args: &[String],
+    mut show: impl FnMut(Report),
+) -> Result<Infallible, CliError> {
+    let mut args = args.to_vec();
+    let bound = match take_option(&mut args, "--bound")? {
+        Some(bound) => parse_number(&bound)?,
+        None => CHECK_BOUND,
+    };
+    if args.is_empty() {
+        return Err(CliError::Usage(
+            "ryvu watch <path> [property]... [--bound n]",
+        ));
+    }
+    let path = Path::new(&args[0]);
+    let mut compiler = IncrementalCompiler::new(true, true);
+    let mut last_change = None;
+    loop {
+        let modified = fs::metadata(path).and_then(|metadata| metadata.modified());
+        if let Ok(modified) = modified {
+            if last_change != Some(modified) {
+                last_change = Some(modified);
+                if let Ok(source) = fs::read_to_string(path) {
+                    let mut report = watched(compiler.compile(&source), &args[1..], bound);
+                    let _ = writeln!(report.text, "reused {} statement groups", compiler.reused());
+                    show(report);
+                }
+            }
+        }
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+fn watched(cr: CompilationResult, properties: &[String], bound: usize) -> Report {
+    let module = match cr.module {
+        Some(ref module) => module,
+        None => {
+            return Report {
+                notes: compile_errors(&cr) + "\n",
+                ..Report::default()
+            }
+        }
+    };
+    let mut report = Report::default();
+    let _ = writeln!(report.text, "compiled {} nodes", module.connections.len());
+    let outputs = cr.output_ids.unwrap_or_default();
+    for text in properties {
+        match parse_property(text, &outputs).map(|property| check(module, &property, bound)) {
+            Ok(Check::Holds(ticks)) => {
+                let _ = writeln!(report.text, "{}: holds for {} ticks", text, ticks);
+            }
+            Ok(Check::Violated(trace)) => {
+                let _ = writeln!(report.text, "{}: violated at tick {}", text, trace.len());
+            }
+            Err(err) => {
+                let _ = writeln!(report.notes, "{}: {}", text, property_error(err));
+            }
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use crate::cli::{compile_source, verify::fault_text};
+
+    #[test]
+    fn fault_report() {
+        let (module, symbols) = compile_source("$a > b > $o").unwrap();
+        let text = fault_text(&module, &symbols, &[vec![vec![true], vec![false]]], Some(1));
+        assert!(text.starts_with("a stuck at 1: undetected\na stuck at 0: detected by vectors 1\n"));
+        assert!(text.contains("o flipped at tick 1: undetected\n"));
+        assert!(text.ends_with("fault coverage: 9/11\n"));
+    }
+}
@@ -0,0 +1,254 @@
+use compile::SourceMap;
+use module::Module;
+use sim::coverage::Coverage;
+use std::fmt::Write;
+
+/// Renders coverage against the source the module was compiled from.
+pub struct CoverageReport<'a> {
+    pub module: &'a Module,
+    pub coverage: &'a Coverage,
+    pub names: &'a [String],
+    pub source: &'a str,
+    pub source_map: &'a SourceMap,
+}
+
+#[derive(Default)]
+struct LineCoverage {
+    items: usize,
+    uncharged: Vec<String>,
+    unblocked: Vec<String>,
+    unfired: Vec<String>,
+}
+
+impl<'a> CoverageReport<'a> {
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        self.summary(&mut text);
+        text.push('\n');
+        let lines = self.lines();
+        for (number, line) in self.source.lines().enumerate() {
+            let coverage = lines.get(number);
+            let mark = match coverage {
+                Some(coverage) if coverage.items > 0 && coverage.is_covered() => '+',
+                Some(coverage) if coverage.items > 0 => '-',
+                _ => ' ',
+            };
+            let _ = writeln!(text, "{:>4} {} {}", number + 1, mark, line);
+            if let Some(coverage) = coverage {
+                for missing in coverage.missing() {
+                    let _ = writeln!(text, "       ! {}", missing);
+                }
+            }
+        }
+        text
+    }
+
+    pub fn html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>ryvu coverage</title>\n<style>\n\
+             pre { margin: 0; }\n\
+             .covered { background: #dfd; }\n\
+             .uncovered { background: #fdd; }\n\
+             .missing { color: #a00; }\n\
+             </style>\n</head>\n<body>\n<pre>\n",
+        );
+        let mut summary = String::new();
+        self.summary(&mut summary);
+        html.push_str(&escape(&summary));
+        html.push_str("</pre>\n<table>\n");
+        let lines = self.lines();
+        for (number, line) in self.source.lines().enumerate() {
+            let coverage = lines.get(number);
+            let class = match coverage {
+                Some(coverage) if coverage.items > 0 && coverage.is_covered() => {
+                    " class=\"covered\""
+                }
+                Some(coverage) if coverage.items > 0 => " class=\"uncovered\"",
+                _ => "",
+            };
+            let missing = coverage.map(|c| c.missing().join("; ")).unwrap_or_default();
+            let _ = writeln!(
+                html,
+                "<tr{}><td>{}</td><td><pre>{}</pre></td><td class=\"missing\">{}</td></tr>",
+                class,
+                number + 1,
+                escape(line),
+                escape(&missing)
+            );
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    fn summary(&self, text: &mut String) {
+        let size = self.module.connections.len();
+        let blockable = self.blockable();
+        let charged = (0..size).filter(|&i| self.coverage.charged[i] > 0).count();
+        let blocked = (0..size)
+            .filter(|&i| blockable[i] && self.coverage.blocked[i] > 0)
+            .count();
+        let mut edges = 0;
+        let mut fired = 0;
+        for (index, con) in self.module.connections.iter().enumerate() {
+            let count = con.charging.len() + con.blocking.len();
+            edges += count;
+            if self.coverage.fired[index] > 0 {
+                fired += count;
+            }
+        }
+        let _ = writeln!(text, "ticks: {}", self.coverage.ticks);
+        let _ = writeln!(text, "nodes charged: {}", ratio(charged, size));
+        let _ = writeln!(
+            text,
+            "nodes blocked: {}",
+            ratio(blocked, blockable.iter().filter(|&&b| b).count())
+        );
+        let _ = writeln!(text, "edges fired: {}", ratio(fired, edges));
+    }
+
+    fn lines(&self) -> Vec<LineCoverage> {
+        let mut lines: Vec<LineCoverage> = vec![];
+        let mut line = |number: usize| {
+            while lines.len() <= number {
+                lines.push(LineCoverage::default());
+            }
+            lines[number].items += 1;
+            number
+        };
+        let mut uncharged = vec![];
+        let mut unblocked = vec![];
+        let mut unfired = vec![];
+        let blockable = self.blockable();
+        for (index, &number) in self.source_map.nodes.iter().enumerate() {
+            let number = line(number);
+            if self.coverage.charged[index] == 0 {
+                uncharged.push((number, self.name(index)));
+            }
+            if blockable[index] && self.coverage.blocked[index] == 0 {
+                unblocked.push((number, self.name(index)));
+            }
+        }
+        let mut edges: Vec<_> = self.source_map.edges.iter().collect();
+        edges.sort();
+        for (&(from, to, is_charge), &number) in edges {
+            let number = line(number);
+            if self.coverage.fired[from] == 0 {
+                let sign = if is_charge { ">" } else { "." };
+                let edge = format!("{} {} {}", self.name(from), sign, self.name(to));
+                unfired.push((number, edge));
+            }
+        }
+        for (number, name) in uncharged {
+            lines[number].uncharged.push(name);
+        }
+        for (number, name) in unblocked {
+            lines[number].unblocked.push(name);
+        }
+        for (number, edge) in unfired {
+            lines[number].unfired.push(edge);
+        }
+        lines
+    }
+
+    fn blockable(&self) -> Vec<bool> {
+        let mut blockable = vec![false; self.module.connections.len()];
+        for con in self.module.connections.iter() {
+            for &to in con.blocking.iter() {
+                blockable[to] = true;
+            }
+        }
+        blockable
+    }
+
+    fn name(&self, index: usize) -> String {
+        self.names
+            .get(index)
+            .cloned()
+            .unwrap_or_else(|| index.to_string())
+    }
+}
+
+impl LineCoverage {
+    fn is_covered(&self) -> bool {
+        self.uncharged.is_empty() && self.unblocked.is_empty() && self.unfired.is_empty()
+    }
+
+    fn missing(&self) -> Vec<String> {
+        let mut missing = vec![];
+        if !self.uncharged.is_empty() {
+            missing.push(format!("never charged: {}", self.uncharged.join(", ")));
+        }
+        if !self.unblocked.is_empty() {
+            missing.push(format!("never blocked: {}", self.unblocked.join(", ")));
+        }
+        if !self.unfired.is_empty() {
+            missing.push(format!("never fired: {}", self.unfired.join(", ")));
+        }
+        missing
+    }
+}
+
+fn ratio(covered: usize, total: usize) -> String {
+    if total == 0 {
+        return "0/0".to_owned();
+    }
+    format!("{}/{} ({}%)", covered, total, covered * 100 / total)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::coverage::CoverageReport;
+    use compile::compile;
+    use sim::network::Network;
+
+    const SOURCE: &str = "$a > b > $o\nb . c\n$r . c";
+
+    fn run(vectors: &[&[usize]]) -> (String, String) {
+        let cr = compile(SOURCE, true, false);
+        let mut network = Network::new(cr.module.unwrap());
+        network.enable_coverage();
+        for charged in vectors {
+            for &index in charged.iter() {
+                network.charge(index);
+            }
+            network.next();
+        }
+        let module = network.module();
+        let report = CoverageReport {
+            module,
+            coverage: network.coverage().unwrap(),
+            names: cr.node_ids.as_ref().unwrap(),
+            source: SOURCE,
+            source_map: cr.source_map.as_ref().unwrap(),
+        };
+        (report.text(), report.html())
+    }
+
+    #[test]
+    fn text_report() {
+        let (text, _) = run(&[&[0], &[]]);
+        assert_eq!(
+            text,
+            "ticks: 2\nnodes charged: 3/5 (60%)\nnodes blocked: 1/1 (100%)\n\
+             edges fired: 3/4 (75%)\n\n   \
+             1 + $a > b > $o\n   \
+             2 - b . c\n       ! never charged: c\n   \
+             3 - $r . c\n       ! never charged: r\n       ! never fired: r . c\n"
+        );
+    }
+
+    #[test]
+    fn html_report() {
+        let (_, html) = run(&[&[0], &[], &[4], &[]]);
+        assert!(html.contains("<tr class=\"covered\"><td>1</td><td><pre>$a &gt; b &gt; $o</pre>"));
+        assert!(html.contains("<td class=\"missing\">never charged: c</td>"));
+    }
+}
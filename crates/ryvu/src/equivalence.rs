@@ -0,0 +1,195 @@
+use crate::reach::bits;
+use module::Module;
+use sim::network::Network;
+use std::collections::HashSet;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Equivalence {
+    /// Every reachable pair of states was explored without a mismatch.
+    Proven,
+    /// No mismatch within the given number of ticks, but unexplored states
+    /// remain.
+    Bounded(usize),
+    /// Input bits, one vector per tick, after which the outputs differ.
+    Counterexample(Vec<Vec<bool>>),
+}
+
+/// The most inputs `equivalence` and `reach::explore` enumerate. Every
+/// explored state is stepped with each of the 2^n input vectors, so past
+/// this even a handful of states takes too long.
+pub const MAX_INPUTS: usize = 16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EquivalenceError {
+    InputCount(usize, usize),
+    OutputCount(usize, usize),
+    /// More inputs than `MAX_INPUTS`.
+    TooManyInputs(usize),
+}
+
+struct Visit {
+    parent: Option<usize>,
+    input: u64,
+}
+
+/// Explores the product of both modules' state spaces breadth first from
+/// reset, trying every input combination on every tick, and compares the
+/// outputs positionally after each tick. The cost is exponential in the
+/// number of inputs, so modules with more than `MAX_INPUTS` are refused.
+pub fn equivalence(
+    left: Module,
+    right: Module,
+    max_ticks: usize,
+) -> Result<Equivalence, EquivalenceError> {
+    if left.inputs.len() != right.inputs.len() {
+        return Err(EquivalenceError::InputCount(
+            left.inputs.len(),
+            right.inputs.len(),
+        ));
+    }
+    if left.outputs.len() != right.outputs.len() {
+        return Err(EquivalenceError::OutputCount(
+            left.outputs.len(),
+            right.outputs.len(),
+        ));
+    }
+    let input_count = left.inputs.len();
+    if input_count > MAX_INPUTS {
+        return Err(EquivalenceError::TooManyInputs(input_count));
+    }
+    let mut left = Network::new(left);
+    let mut right = Network::new(right);
+    let mut visits = vec![Visit {
+        parent: None,
+        input: 0,
+    }];
+    let mut visited = HashSet::new();
+    let mut frontier = vec![(0, left.snapshot(), right.snapshot())];
+    visited.insert((left.snapshot(), right.snapshot()));
+    for _ in 0..max_ticks {
+        if frontier.is_empty() {
+            return Ok(Equivalence::Proven);
+        }
+        let mut next_frontier = vec![];
+        for (visit, left_states, right_states) in frontier {
+            for input in 0..(1u64 << input_count) {
+                left.load(&left_states)
+                    .expect("states come from this network");
+                right
+                    .load(&right_states)
+                    .expect("states come from this network");
+                let input_data = bits(input, input_count);
+                left.apply(&input_data);
+                right.apply(&input_data);
+                visits.push(Visit {
+                    parent: Some(visit),
+                    input,
+                });
+                if left.outputs() != right.outputs() {
+                    return Ok(Equivalence::Counterexample(trace(
+                        &visits,
+                        visits.len() - 1,
+                        input_count,
+                    )));
+                }
+                let key = (left.snapshot(), right.snapshot());
+                if visited.insert(key.clone()) {
+                    next_frontier.push((visits.len() - 1, key.0, key.1));
+                } else {
+                    visits.pop();
+                }
+            }
+        }
+        frontier = next_frontier;
+    }
+    if frontier.is_empty() {
+        Ok(Equivalence::Proven)
+    } else {
+        Ok(Equivalence::Bounded(max_ticks))
+    }
+}
+
+fn trace(visits: &[Visit], mut visit: usize, input_count: usize) -> Vec<Vec<bool>> {
+    let mut trace = vec![];
+    while let Some(parent) = visits[visit].parent {
+        let input = visits[visit].input;
+        trace.push(bits(input, input_count));
+        visit = parent;
+    }
+    trace.reverse();
+    trace
+}
+
+#[cfg(test)]
+mod test {
+    use crate::equivalence::{equivalence, Equivalence, EquivalenceError, MAX_INPUTS};
+    use module::{Module, ModuleBuilder};
+
+    fn relay(hops: usize) -> Module {
+        let mut builder = ModuleBuilder::default();
+        for i in 0..hops {
+            builder.charge(i, i + 1);
+        }
+        builder.input(0);
+        builder.output(hops);
+        builder.build()
+    }
+
+    #[test]
+    fn renamed_circuits_are_equivalent() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(2, 0);
+        builder.charge(0, 1);
+        builder.input(2);
+        builder.output(1);
+        assert_eq!(
+            equivalence(relay(2), builder.build(), 100),
+            Ok(Equivalence::Proven)
+        );
+    }
+
+    #[test]
+    fn different_delays_differ() {
+        assert_eq!(
+            equivalence(relay(2), relay(3), 100),
+            Ok(Equivalence::Counterexample(vec![vec![true], vec![false]]))
+        );
+    }
+
+    #[test]
+    fn bounded_search() {
+        assert_eq!(
+            equivalence(relay(2), relay(3), 1),
+            Ok(Equivalence::Bounded(1))
+        );
+    }
+
+    #[test]
+    fn port_count_mismatch() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.input(0);
+        builder.input(1);
+        assert_eq!(
+            equivalence(relay(1), builder.build(), 100),
+            Err(EquivalenceError::InputCount(1, 2))
+        );
+    }
+
+    #[test]
+    fn input_limit() {
+        let wide = || {
+            let mut builder = ModuleBuilder::default();
+            for input in 0..=MAX_INPUTS {
+                builder.charge(input, MAX_INPUTS + 1);
+                builder.input(input);
+            }
+            builder.output(MAX_INPUTS + 1);
+            builder.build()
+        };
+        assert_eq!(
+            equivalence(wide(), wide(), 100),
+            Err(EquivalenceError::TooManyInputs(MAX_INPUTS + 1))
+        );
+    }
+}
@@ -0,0 +1,205 @@
+use module::Module;
+use sim::network::Network;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Fault {
+    StuckCharged(usize),
+    StuckDischarged(usize),
+    /// A charging edge, given by its source and target, that never fires.
+    DropCharge(usize, usize),
+    /// A blocking edge, given by its source and target, that never fires.
+    DropBlock(usize, usize),
+    /// The charge of a node inverted just before the given tick, counted
+    /// from 1, propagates. A tick of 0 never comes, so the fault never fires.
+    Flip {
+        node: usize,
+        tick: usize,
+    },
+}
+
+/// Which test vectors detect each fault.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FaultReport {
+    pub faults: Vec<Fault>,
+    /// Positions of the detecting vectors, for each fault.
+    pub detected_by: Vec<Vec<usize>>,
+}
+
+/// Every stuck-at fault and every edge removal of the module.
+pub fn faults(module: &Module) -> Vec<Fault> {
+    let mut faults = vec![];
+    for index in 0..module.connections.len() {
+        faults.push(Fault::StuckCharged(index));
+        faults.push(Fault::StuckDischarged(index));
+    }
+    for (index, con) in module.connections.iter().enumerate() {
+        let mut charging: Vec<usize> = con.charging.iter().copied().collect();
+        charging.sort_unstable();
+        faults.extend(charging.into_iter().map(|to| Fault::DropCharge(index, to)));
+        let mut blocking: Vec<usize> = con.blocking.iter().copied().collect();
+        blocking.sort_unstable();
+        faults.extend(blocking.into_iter().map(|to| Fault::DropBlock(index, to)));
+    }
+    faults
+}
+
+/// Runs every vector, a sequence of input bits per tick starting from reset,
+/// against the fault-free module and each faulty one. A vector detects a
+/// fault when any output differs on any tick.
+pub fn simulate_faults(
+    module: &Module,
+    faults: &[Fault],
+    vectors: &[Vec<Vec<bool>>],
+) -> FaultReport {
+    let mut golden = Network::new(module.clone());
+    let expected: Vec<Vec<Vec<bool>>> = vectors
+        .iter()
+        .map(|vector| run(&mut golden, None, vector))
+        .collect();
+    let detected_by = faults
+        .iter()
+        .map(|fault| {
+            let mut network = Network::new(faulty(module, fault));
+            (0..vectors.len())
+                .filter(|&position| {
+                    run(&mut network, Some(fault), &vectors[position]) != expected[position]
+                })
+                .collect()
+        })
+        .collect();
+    FaultReport {
+        faults: faults.to_vec(),
+        detected_by,
+    }
+}
+
+fn faulty(module: &Module, fault: &Fault) -> Module {
+    let mut module = module.clone();
+    match *fault {
+        Fault::DropCharge(from, to) => {
+            module.connections[from].charging.remove(&to);
+        }
+        Fault::DropBlock(from, to) => {
+            module.connections[from].blocking.remove(&to);
+        }
+        _ => {}
+    }
+    module
+}
+
+/// Where in a tick a fault is applied.
+#[derive(Clone, Copy)]
+enum Point {
+    /// Before the given tick, counted from 1, propagates.
+    Before(usize),
+    /// After a tick, so that stuck nodes also read as stuck.
+    After,
+}
+
+/// `Network::replay`, with the fault injected between charging the inputs and
+/// ticking.
+fn run(network: &mut Network, fault: Option<&Fault>, vector: &[Vec<bool>]) -> Vec<Vec<bool>> {
+    network.reset();
+    let mut outputs = vec![];
+    for (tick, bits) in vector.iter().enumerate() {
+        network.charge_inputs(bits);
+        inject(network, fault, Point::Before(tick + 1));
+        network.next();
+        inject(network, fault, Point::After);
+        outputs.push(network.outputs());
+    }
+    outputs
+}
+
+fn inject(network: &mut Network, fault: Option<&Fault>, point: Point) {
+    match (fault, point) {
+        (Some(&Fault::StuckCharged(node)), _) => network.force(node, true),
+        (Some(&Fault::StuckDischarged(node)), _) => network.force(node, false),
+        (Some(&Fault::Flip { node, tick }), Point::Before(at)) if at == tick => {
+            let charged = network.seek(node);
+            network.force(node, !charged);
+        }
+        _ => {}
+    }
+}
+
+impl FaultReport {
+    /// The number of faults detected by at least one vector.
+    pub fn detected(&self) -> usize {
+        self.detected_by.iter().filter(|by| !by.is_empty()).count()
+    }
+
+    pub fn undetected(&self) -> Vec<Fault> {
+        self.faults
+            .iter()
+            .zip(self.detected_by.iter())
+            .filter(|(_, by)| by.is_empty())
+            .map(|(&fault, _)| fault)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fault::{faults, run, simulate_faults, Fault};
+    use module::{Module, ModuleBuilder};
+    use sim::network::Network;
+
+    /// `$a > b > $o` with `$c . b`.
+    fn gate() -> Module {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.block(3, 1);
+        builder.input(0);
+        builder.input(3);
+        builder.output(2);
+        builder.build()
+    }
+
+    #[test]
+    fn enumerates_faults() {
+        let faults = faults(&gate());
+        assert_eq!(faults.len(), 11);
+        assert!(faults.contains(&Fault::DropBlock(3, 1)));
+    }
+
+    #[test]
+    fn vectors_detect_faults() {
+        let module = gate();
+        let faults = vec![
+            Fault::StuckDischarged(1),
+            Fault::DropBlock(3, 1),
+            Fault::StuckCharged(3),
+            Fault::Flip { node: 1, tick: 2 },
+        ];
+        let pass = vec![vec![true, false], vec![false, false]];
+        let block = vec![vec![true, true], vec![false, false]];
+        let report = simulate_faults(&module, &faults, &[pass, block]);
+        assert_eq!(report.detected_by, vec![vec![0], vec![1], vec![0], vec![0]]);
+        assert_eq!(report.detected(), 4);
+    }
+
+    #[test]
+    fn undetected_faults() {
+        let module = gate();
+        let faults = faults(&module);
+        let pass = vec![vec![true, false], vec![false, false]];
+        let report = simulate_faults(&module, &faults, &[pass]);
+        assert!(report.undetected().contains(&Fault::DropBlock(3, 1)));
+        assert!(!report.undetected().contains(&Fault::DropCharge(0, 1)));
+    }
+
+    #[test]
+    fn flips_once() {
+        let mut network = Network::new(gate());
+        let idle = vec![vec![false, false]; 3];
+        let flip = Fault::Flip { node: 1, tick: 1 };
+        assert_eq!(
+            run(&mut network, Some(&flip), &idle),
+            vec![vec![true], vec![false], vec![false]]
+        );
+        let never = Fault::Flip { node: 1, tick: 0 };
+        assert_eq!(run(&mut network, Some(&never), &idle), vec![vec![false]; 3]);
+    }
+}
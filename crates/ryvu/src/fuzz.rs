@@ -0,0 +1,190 @@
+use crate::check::Property;
+use module::Module;
+use sim::{network::Network, random::Rng};
+
+/// Decides whether the outputs seen on a tick are acceptable.
+pub trait Oracle {
+    /// Called before every trace is replayed.
+    fn reset(&mut self);
+    /// Called after every tick, starting from tick 1.
+    fn accepts(&mut self, tick: usize, inputs: &[bool], outputs: &[bool]) -> bool;
+}
+
+/// Accepts the outputs while they match those of a reference module driven
+/// by the same inputs. Ports are matched positionally.
+pub struct Reference {
+    network: Network,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct FuzzConfig {
+    pub seed: u64,
+    /// Number of random traces to try.
+    pub runs: usize,
+    /// Length of each random trace, in ticks.
+    pub ticks: usize,
+}
+
+/// Replays random input traces and returns the first one the oracle rejects,
+/// shrunk to a minimal reproduction.
+pub fn fuzz<O: Oracle>(
+    module: Module,
+    oracle: &mut O,
+    config: &FuzzConfig,
+) -> Option<Vec<Vec<bool>>> {
+    let input_count = module.inputs.len();
+    let mut network = Network::new(module);
+    let mut rng = Rng::new(config.seed);
+    for _ in 0..config.runs {
+        let trace: Vec<Vec<bool>> = (0..config.ticks)
+            .map(|_| (0..input_count).map(|_| rng.next_u64() & 1 == 1).collect())
+            .collect();
+        if let Some(tick) = failing_tick(&mut network, oracle, &trace) {
+            return Some(shrink(&mut network, oracle, trace[..tick].to_vec()));
+        }
+    }
+    None
+}
+
+/// Replays `trace` from reset, returning the number of ticks after which the
+/// oracle rejected the outputs.
+fn failing_tick<O: Oracle>(
+    network: &mut Network,
+    oracle: &mut O,
+    trace: &[Vec<bool>],
+) -> Option<usize> {
+    network.reset();
+    oracle.reset();
+    for (tick, bits) in trace.iter().enumerate() {
+        network.apply(bits);
+        if !oracle.accepts(tick + 1, bits, &network.outputs()) {
+            return Some(tick + 1);
+        }
+    }
+    None
+}
+
+/// Removes chunks of ticks, then clears single input bits, for as long as the
+/// trace keeps failing.
+fn shrink<O: Oracle>(
+    network: &mut Network,
+    oracle: &mut O,
+    mut trace: Vec<Vec<bool>>,
+) -> Vec<Vec<bool>> {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut chunk = trace.len() / 2;
+        while chunk > 0 {
+            let mut start = 0;
+            while start + chunk <= trace.len() {
+                let mut candidate = trace[..start].to_vec();
+                candidate.extend_from_slice(&trace[start + chunk..]);
+                match failing_tick(network, oracle, &candidate) {
+                    Some(tick) => {
+                        candidate.truncate(tick);
+                        trace = candidate;
+                        changed = true;
+                    }
+                    None => start += chunk,
+                }
+            }
+            chunk /= 2;
+        }
+        for tick in 0..trace.len() {
+            for position in 0..trace[tick].len() {
+                if trace[tick][position] {
+                    trace[tick][position] = false;
+                    match failing_tick(network, oracle, &trace) {
+                        Some(_) => changed = true,
+                        None => trace[tick][position] = true,
+                    }
+                }
+            }
+        }
+    }
+    trace
+}
+
+impl Oracle for Property {
+    fn reset(&mut self) {}
+
+    fn accepts(&mut self, tick: usize, _: &[bool], outputs: &[bool]) -> bool {
+        self.holds(tick, outputs)
+    }
+}
+
+impl Reference {
+    pub fn new(module: Module) -> Reference {
+        Reference {
+            network: Network::new(module),
+        }
+    }
+}
+
+impl Oracle for Reference {
+    fn reset(&mut self) {
+        self.network.reset();
+    }
+
+    fn accepts(&mut self, _: usize, inputs: &[bool], outputs: &[bool]) -> bool {
+        self.network.apply(inputs);
+        self.network.outputs() == outputs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        check::parse_property,
+        fuzz::{fuzz, FuzzConfig, Reference},
+    };
+    use module::{Module, ModuleBuilder};
+
+    const CONFIG: FuzzConfig = FuzzConfig {
+        seed: 7,
+        runs: 100,
+        ticks: 20,
+    };
+
+    /// `$s > q > q > $o` with `$r . q`: a latch that `$r` clears.
+    fn latch() -> Module {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 1);
+        builder.block(2, 1);
+        builder.charge(1, 3);
+        builder.input(0);
+        builder.input(2);
+        builder.output(3);
+        builder.build()
+    }
+
+    #[test]
+    fn property_failure_is_shrunk() {
+        let mut property = parse_property("never $o", &["o".to_owned()]).unwrap();
+        let trace = fuzz(latch(), &mut property, &CONFIG).unwrap();
+        assert_eq!(trace, vec![vec![true, false], vec![false, false]]);
+    }
+
+    #[test]
+    fn reference_mismatch_is_shrunk() {
+        // Same latch, but `$r` has no effect.
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 1);
+        builder.charge(1, 3);
+        builder.input(0);
+        builder.input(2);
+        builder.output(3);
+        let mut reference = Reference::new(builder.build());
+        let trace = fuzz(latch(), &mut reference, &CONFIG).unwrap();
+        assert_eq!(trace, vec![vec![true, true], vec![false, false]]);
+    }
+
+    #[test]
+    fn identical_modules_pass() {
+        let mut reference = Reference::new(latch());
+        assert_eq!(fuzz(latch(), &mut reference, &CONFIG), None);
+    }
+}
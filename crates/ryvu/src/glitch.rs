@@ -0,0 +1,215 @@
+use sim::{
+    network::Network,
+    settle::{settle, Settling},
+};
+use std::{collections::HashMap, mem};
+
+/// Outputs that toggled more than once after an input change.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GlitchReport {
+    /// How the network behaved under the new inputs.
+    pub settling: Settling,
+    pub glitches: Vec<Glitch>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Glitch {
+    /// Position of the output among the module outputs.
+    pub output: usize,
+    pub toggles: Vec<Toggle>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Toggle {
+    /// Ticks elapsed since the input change.
+    pub tick: usize,
+    /// Value of the output after the toggle.
+    pub value: bool,
+    /// Edges charging the output whose source changed activity on the tick
+    /// before, and edges blocking one of those sources whose own source
+    /// changed activity a tick earlier, as `(from, to, is_charge)` sorted by
+    /// source.
+    pub drivers: Vec<(usize, usize, bool)>,
+}
+
+/// Settles the network under the `before` inputs, switches to the `after`
+/// inputs and runs until the state repeats, recording every toggle of the
+/// module outputs on the way. Returns `None` if either phase does not reach
+/// a repeated state within `max_ticks` ticks.
+pub fn glitches(
+    network: &mut Network,
+    before: &[bool],
+    after: &[bool],
+    max_ticks: usize,
+) -> Option<GlitchReport> {
+    settle(network, before, max_ticks)?;
+    let sources = sources(network);
+    let mut active = activity(network, &sources, &charged_inputs(network, before));
+    let mut earlier = active.clone();
+    let mut values = network.outputs();
+    let mut toggles: Vec<Vec<Toggle>> = values.iter().map(|_| vec![]).collect();
+    let mut seen = HashMap::new();
+    seen.insert(network.snapshot(), 0);
+    for tick in 1..=max_ticks {
+        network.set_inputs(after);
+        let current = activity(network, &sources, &[]);
+        network.next();
+        for (position, value) in network.outputs().into_iter().enumerate() {
+            if value != values[position] {
+                let drivers = (0..sources[position].len())
+                    .filter(|&edge| {
+                        if sources[position][edge].2 {
+                            active[position][edge] != current[position][edge]
+                        } else {
+                            earlier[position][edge] != active[position][edge]
+                        }
+                    })
+                    .map(|edge| sources[position][edge])
+                    .collect();
+                toggles[position].push(Toggle {
+                    tick,
+                    value,
+                    drivers,
+                });
+                values[position] = value;
+            }
+        }
+        earlier = mem::replace(&mut active, current);
+        if let Some(&first) = seen.get(&network.snapshot()) {
+            let glitches = toggles
+                .into_iter()
+                .enumerate()
+                .filter(|(_, toggles)| toggles.len() > 1)
+                .map(|(output, toggles)| Glitch { output, toggles })
+                .collect();
+            return Some(GlitchReport {
+                settling: Settling {
+                    transient: first,
+                    period: tick - first,
+                },
+                glitches,
+            });
+        }
+        seen.insert(network.snapshot(), tick);
+    }
+    None
+}
+
+/// The edges that can toggle each output: those charging it, and those
+/// blocking a node that charges it, as `(from, to, is_charge)`.
+fn sources(network: &Network) -> Vec<Vec<(usize, usize, bool)>> {
+    let connections = &network.module().connections;
+    let into = |to: usize, is_charge: bool| {
+        (0..connections.len()).filter(move |&from| {
+            let targets = if is_charge {
+                &connections[from].charging
+            } else {
+                &connections[from].blocking
+            };
+            targets.contains(&to)
+        })
+    };
+    network
+        .module()
+        .outputs
+        .iter()
+        .map(|&output| {
+            let mut edges = vec![];
+            for from in into(output, true) {
+                edges.push((from, output, true));
+                edges.extend(into(from, false).map(|blocker| (blocker, from, false)));
+            }
+            edges.sort_unstable();
+            edges
+        })
+        .collect()
+}
+
+fn charged_inputs(network: &Network, input_data: &[bool]) -> Vec<usize> {
+    network
+        .module()
+        .inputs
+        .iter()
+        .zip(input_data)
+        .filter(|(_, &bit)| bit)
+        .map(|(&index, _)| index)
+        .collect()
+}
+
+/// Whether the source of each edge is charged and unblocked, counting the
+/// inputs about to be charged.
+fn activity(
+    network: &Network,
+    sources: &[Vec<(usize, usize, bool)>],
+    charged: &[usize],
+) -> Vec<Vec<bool>> {
+    sources
+        .iter()
+        .map(|sources| {
+            sources
+                .iter()
+                .map(|&(index, _, _)| {
+                    (network.seek(index) || charged.contains(&index)) && !network.is_blocked(index)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::glitch::{glitches, Toggle};
+    use module::ModuleBuilder;
+    use sim::network::Network;
+
+    #[test]
+    fn unequal_paths_glitch() {
+        // a > x > q > o, and a > y > z . q blocks q once the longer path
+        // arrives.
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(0, 2);
+        builder.charge(2, 3);
+        builder.block(3, 4);
+        builder.charge(1, 4);
+        builder.charge(4, 5);
+        builder.input(0);
+        builder.output(5);
+
+        let mut network = Network::new(builder.build());
+        let report = glitches(&mut network, &[false], &[true], 100).unwrap();
+        assert!(report.settling.is_stable());
+        assert_eq!(report.glitches.len(), 1);
+        assert_eq!(
+            report.glitches[0].toggles,
+            vec![
+                Toggle {
+                    tick: 3,
+                    value: true,
+                    drivers: vec![(4, 5, true)]
+                },
+                Toggle {
+                    tick: 4,
+                    value: false,
+                    drivers: vec![(3, 4, false), (4, 5, true)]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn balanced_paths_do_not_glitch() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(0, 3);
+        builder.charge(3, 2);
+        builder.input(0);
+        builder.output(2);
+
+        let mut network = Network::new(builder.build());
+        let report = glitches(&mut network, &[false], &[true], 100).unwrap();
+        assert_eq!(report.glitches, vec![]);
+        assert_eq!(report.settling.transient, 2);
+    }
+}
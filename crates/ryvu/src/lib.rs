@@ -0,0 +1,9 @@
+pub mod check;
+pub mod cli;
+pub mod coverage;
+pub mod equivalence;
+pub mod fault;
+pub mod fuzz;
+pub mod glitch;
+pub mod loops;
+pub mod reach;
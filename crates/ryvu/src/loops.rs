@@ -0,0 +1,78 @@
+use module::{scc::loops, Module};
+
+/// A strongly connected component of the charge/block graph, the only place
+/// a circuit can hold or cycle state.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FeedbackLoop {
+    pub kind: LoopKind,
+    /// Member nodes, sorted by index.
+    pub nodes: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LoopKind {
+    /// Only charging edges inside the loop. A ring of several nodes passes
+    /// its charge around and oscillates; a single node holds its charge.
+    ChargeRing,
+    /// Some member blocks another member, so the loop can latch a value.
+    Latch,
+    /// Some member blocks itself.
+    SelfBlocking,
+}
+
+/// Every feedback loop of the module, ordered by first member. A loop with a
+/// self-blocking member counts as self-blocking even if other members block
+/// each other.
+pub fn feedback_loops(module: &Module) -> Vec<FeedbackLoop> {
+    loops(module)
+        .into_iter()
+        .map(|nodes| {
+            let blocks = |from: usize, to: usize| module.connections[from].blocking.contains(&to);
+            let kind = if nodes.iter().any(|&node| blocks(node, node)) {
+                LoopKind::SelfBlocking
+            } else if nodes
+                .iter()
+                .any(|&from| nodes.iter().any(|&to| blocks(from, to)))
+            {
+                LoopKind::Latch
+            } else {
+                LoopKind::ChargeRing
+            };
+            FeedbackLoop { kind, nodes }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::loops::{feedback_loops, FeedbackLoop, LoopKind};
+    use compile::compile;
+
+    #[test]
+    fn classifies_loops() {
+        let source = "$s > q > q\n$r . q\na > b > c > a\nx > y . x\nn . n";
+        let cr = compile(source, true, false);
+        let names = cr.node_ids.unwrap();
+        let named = |nodes: &[usize]| -> Vec<String> {
+            nodes.iter().map(|&node| names[node].clone()).collect()
+        };
+        let loops = feedback_loops(&cr.module.unwrap());
+        let found: Vec<(LoopKind, Vec<String>)> = loops
+            .iter()
+            .map(|FeedbackLoop { kind, nodes }| (*kind, named(nodes)))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (LoopKind::ChargeRing, strings(&["q"])),
+                (LoopKind::ChargeRing, strings(&["a", "b", "c"])),
+                (LoopKind::Latch, strings(&["x", "y"])),
+                (LoopKind::SelfBlocking, strings(&["n"])),
+            ]
+        );
+    }
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+}
@@ -0,0 +1,238 @@
+use crate::equivalence::MAX_INPUTS;
+use module::Module;
+use sim::network::{Network, NodeState};
+use std::{collections::HashMap, fmt::Write};
+
+/// Every state reachable from reset, with the transitions between them.
+pub struct StateGraph {
+    /// Explored states; the first one is the reset state.
+    pub states: Vec<State>,
+    pub transitions: Vec<Transition>,
+    /// Whether exploration finished before hitting the state limit.
+    pub complete: bool,
+    input_count: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct State(Vec<NodeState>);
+
+/// All the input combinations that lead from one state to another.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Transition {
+    pub from: usize,
+    pub to: usize,
+    /// Input bits as masks, bit `i` standing for the `i`th input.
+    pub inputs: Vec<u64>,
+}
+
+/// More inputs than `MAX_INPUTS`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TooManyInputs(pub usize);
+
+/// Explores the state space breadth first from reset, trying every input
+/// combination in every state, until no new state is found or `max_states`
+/// states have been explored. Modules with more than `MAX_INPUTS` inputs are
+/// refused.
+pub fn explore(module: Module, max_states: usize) -> Result<StateGraph, TooManyInputs> {
+    let input_count = module.inputs.len();
+    if input_count > MAX_INPUTS {
+        return Err(TooManyInputs(input_count));
+    }
+    let mut network = Network::new(module);
+    let mut graph = StateGraph {
+        states: vec![State(network.snapshot())],
+        transitions: vec![],
+        complete: true,
+        input_count,
+    };
+    let mut indexes = HashMap::new();
+    indexes.insert(network.snapshot(), 0);
+    let mut current = 0;
+    while current < graph.states.len() {
+        let mut targets: Vec<(usize, Vec<u64>)> = vec![];
+        for input in 0..(1u64 << input_count) {
+            network
+                .load(&graph.states[current].0)
+                .expect("states come from this network");
+            network.apply(&bits(input, input_count));
+            let snapshot = network.snapshot();
+            let target = match indexes.get(&snapshot) {
+                Some(&index) => index,
+                None if graph.states.len() < max_states => {
+                    indexes.insert(snapshot.clone(), graph.states.len());
+                    graph.states.push(State(snapshot));
+                    graph.states.len() - 1
+                }
+                None => {
+                    graph.complete = false;
+                    continue;
+                }
+            };
+            match targets.iter_mut().find(|(to, _)| *to == target) {
+                Some((_, inputs)) => inputs.push(input),
+                None => targets.push((target, vec![input])),
+            }
+        }
+        for (to, inputs) in targets {
+            graph.transitions.push(Transition {
+                from: current,
+                to,
+                inputs,
+            });
+        }
+        current += 1;
+    }
+    Ok(graph)
+}
+
+impl State {
+    pub fn is_charged(&self, index: usize) -> bool {
+        self.0[index].is_charged()
+    }
+
+    pub fn is_blocked(&self, index: usize) -> bool {
+        self.0[index].is_blocked()
+    }
+}
+
+impl StateGraph {
+    /// States that every input combination leads back to.
+    pub fn sinks(&self) -> Vec<usize> {
+        let mut outgoing = vec![0; self.states.len()];
+        let mut looping = vec![false; self.states.len()];
+        for transition in self.transitions.iter() {
+            outgoing[transition.from] += 1;
+            if transition.to == transition.from
+                && transition.inputs.len() as u64 == 1 << self.input_count
+            {
+                looping[transition.from] = true;
+            }
+        }
+        (0..self.states.len())
+            .filter(|&state| outgoing[state] == 1 && looping[state])
+            .collect()
+    }
+
+    /// Renders the graph in Graphviz DOT format, labelling each state with
+    /// its charged nodes (`*` marking blocked ones) and each edge with its
+    /// input bits.
+    pub fn to_dot(&self, names: &[String]) -> String {
+        let mut dot = String::from("digraph states {\n");
+        for (index, state) in self.states.iter().enumerate() {
+            let mut nodes = vec![];
+            for node in 0..state.0.len() {
+                if state.is_charged(node) || state.is_blocked(node) {
+                    let name = names.get(node).cloned().unwrap_or_else(|| node.to_string());
+                    let mark = if state.is_blocked(node) { "*" } else { "" };
+                    nodes.push(format!("{}{}", name, mark));
+                }
+            }
+            let _ = writeln!(dot, "    s{} [label=\"{}\"];", index, nodes.join(" "));
+        }
+        for transition in self.transitions.iter() {
+            let labels: Vec<String> = transition
+                .inputs
+                .iter()
+                .map(|&input| {
+                    bits(input, self.input_count)
+                        .into_iter()
+                        .map(|bit| if bit { '1' } else { '0' })
+                        .collect()
+                })
+                .collect();
+            let _ = writeln!(
+                dot,
+                "    s{} -> s{} [label=\"{}\"];",
+                transition.from,
+                transition.to,
+                labels.join(",")
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// The first `count` bits of an input mask, one per input position.
+pub(crate) fn bits(input: u64, count: usize) -> Vec<bool> {
+    (0..count).map(|i| input & (1 << i) != 0).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        equivalence::MAX_INPUTS,
+        reach::{explore, TooManyInputs},
+    };
+    use module::ModuleBuilder;
+
+    #[test]
+    fn latch_states() {
+        // $s > q, q > q, $r . q
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 1);
+        builder.block(2, 1);
+        builder.input(0);
+        builder.input(2);
+        let graph = explore(builder.build(), 100).unwrap();
+        assert!(graph.complete);
+        assert_eq!(graph.states.len(), 4);
+        assert!(graph.states[1].is_charged(1) && !graph.states[1].is_blocked(1));
+        assert!(!graph.states[2].is_charged(1) && graph.states[2].is_blocked(1));
+        assert!(graph.states[3].is_charged(1) && graph.states[3].is_blocked(1));
+        assert_eq!(graph.sinks(), vec![]);
+    }
+
+    #[test]
+    fn unstoppable_ring_is_a_sink() {
+        // $a > x, x > x
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 1);
+        builder.input(0);
+        let graph = explore(builder.build(), 100).unwrap();
+        assert_eq!(graph.states.len(), 2);
+        assert_eq!(graph.sinks(), vec![1]);
+    }
+
+    #[test]
+    fn state_limit() {
+        let mut builder = ModuleBuilder::default();
+        for i in 0..8 {
+            builder.charge(i, i + 1);
+        }
+        builder.input(0);
+        let graph = explore(builder.build(), 10).unwrap();
+        assert!(!graph.complete);
+        assert_eq!(graph.states.len(), 10);
+    }
+
+    #[test]
+    fn input_limit() {
+        let mut builder = ModuleBuilder::default();
+        for input in 0..=MAX_INPUTS {
+            builder.charge(input, MAX_INPUTS + 1);
+            builder.input(input);
+        }
+        assert_eq!(
+            explore(builder.build(), 10).err(),
+            Some(TooManyInputs(MAX_INPUTS + 1))
+        );
+    }
+
+    #[test]
+    fn dot_export() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.input(0);
+        let graph = explore(builder.build(), 10).unwrap();
+        let names = vec!["a".to_owned(), "b".to_owned()];
+        assert_eq!(
+            graph.to_dot(&names),
+            "digraph states {\n    s0 [label=\"\"];\n    s1 [label=\"b\"];\n    \
+             s0 -> s0 [label=\"0\"];\n    s0 -> s1 [label=\"1\"];\n    \
+             s1 -> s0 [label=\"0\"];\n    s1 -> s1 [label=\"1\"];\n}\n"
+        );
+    }
+}
@@ -0,0 +1,530 @@
+use std::ops::Not;
+
+/// A variable or its negation.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct Lit(u32);
+
+/// A CDCL solver with two watched literals, first-UIP clause learning,
+/// activity-based decisions, phase saving and Luby restarts.
+#[derive(Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    phases: Vec<bool>,
+    activity: Vec<f64>,
+    increment: f64,
+    heap: Vec<usize>,
+    positions: Vec<Option<usize>>,
+    seen: Vec<bool>,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    head: usize,
+    model: Vec<bool>,
+    unsatisfiable: bool,
+}
+
+impl Lit {
+    fn new(var: usize, negated: bool) -> Lit {
+        Lit((var as u32) << 1 | negated as u32)
+    }
+
+    pub fn var(self) -> usize {
+        (self.0 >> 1) as usize
+    }
+
+    pub fn is_negated(self) -> bool {
+        self.0 & 1 == 1
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+impl Solver {
+    /// Creates a fresh variable and returns its positive literal.
+    pub fn new_var(&mut self) -> Lit {
+        let var = self.values.len();
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.phases.push(false);
+        self.activity.push(0.0);
+        self.positions.push(None);
+        self.seen.push(false);
+        self.watches.push(vec![]);
+        self.watches.push(vec![]);
+        self.heap_insert(var);
+        Lit::new(var, false)
+    }
+
+    pub fn var_count(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn add_clause(&mut self, lits: &[Lit]) {
+        self.cancel_until(0);
+        if self.unsatisfiable {
+            return;
+        }
+        let mut clause: Vec<Lit> = vec![];
+        for &lit in lits {
+            match self.value(lit) {
+                Some(true) => return,
+                Some(false) => {}
+                None => {
+                    if clause.contains(&!lit) {
+                        return;
+                    }
+                    if !clause.contains(&lit) {
+                        clause.push(lit);
+                    }
+                }
+            }
+        }
+        match clause.len() {
+            0 => self.unsatisfiable = true,
+            1 => {
+                self.assign(clause[0], None);
+                if self.propagate().is_some() {
+                    self.unsatisfiable = true;
+                }
+            }
+            _ => {
+                self.attach(clause);
+            }
+        }
+    }
+
+    pub fn solve(&mut self) -> bool {
+        self.solve_with(&[])
+    }
+
+    /// Solves under the given assumptions, which only hold for this call.
+    pub fn solve_with(&mut self, assumptions: &[Lit]) -> bool {
+        self.cancel_until(0);
+        if self.unsatisfiable {
+            return false;
+        }
+        let mut restart = 0;
+        loop {
+            let budget = 100 * luby(restart);
+            restart += 1;
+            if let Some(result) = self.search(budget, assumptions) {
+                self.cancel_until(0);
+                return result;
+            }
+        }
+    }
+
+    /// The value of `lit` in the model found by the last successful solve.
+    pub fn model_value(&self, lit: Lit) -> bool {
+        self.model[lit.var()] != lit.is_negated()
+    }
+
+    fn search(&mut self, budget: usize, assumptions: &[Lit]) -> Option<bool> {
+        let mut conflicts = 0;
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.trail_lim.is_empty() {
+                    self.unsatisfiable = true;
+                    return Some(false);
+                }
+                conflicts += 1;
+                let (learnt, level) = self.analyze(conflict);
+                self.cancel_until(level);
+                self.learn(learnt);
+                self.increment /= 0.95;
+            } else if conflicts >= budget {
+                self.cancel_until(0);
+                return None;
+            } else if self.trail_lim.len() < assumptions.len() {
+                let lit = assumptions[self.trail_lim.len()];
+                match self.value(lit) {
+                    Some(true) => self.trail_lim.push(self.trail.len()),
+                    Some(false) => return Some(false),
+                    None => {
+                        self.trail_lim.push(self.trail.len());
+                        self.assign(lit, None);
+                    }
+                }
+            } else {
+                match self.decide() {
+                    Some(lit) => {
+                        self.trail_lim.push(self.trail.len());
+                        self.assign(lit, None);
+                    }
+                    None => {
+                        self.model = self.values.iter().map(|v| v == &Some(true)).collect();
+                        return Some(true);
+                    }
+                }
+            }
+        }
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var()].map(|value| value != lit.is_negated())
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var();
+        self.values[var] = Some(!lit.is_negated());
+        self.levels[var] = self.trail_lim.len();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0].index()].push(index);
+        self.watches[clause[1].index()].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    /// Propagates every pending assignment, returning a conflicting clause.
+    fn propagate(&mut self) -> Option<usize> {
+        while self.head < self.trail.len() {
+            let falsified = !self.trail[self.head];
+            self.head += 1;
+            let watching = std::mem::take(&mut self.watches[falsified.index()]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (position, &index) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[position..]);
+                    break;
+                }
+                let clause = &mut self.clauses[index];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if self.values[first.var()].map(|v| v != first.is_negated()) == Some(true) {
+                    kept.push(index);
+                    continue;
+                }
+                let mut moved = false;
+                for k in 2..clause.len() {
+                    let lit = clause[k];
+                    if self.values[lit.var()].map(|v| v != lit.is_negated()) != Some(false) {
+                        clause.swap(1, k);
+                        self.watches[lit.index()].push(index);
+                        moved = true;
+                        break;
+                    }
+                }
+                if moved {
+                    continue;
+                }
+                kept.push(index);
+                match self.value(first) {
+                    Some(false) => conflict = Some(index),
+                    _ => self.assign(first, Some(index)),
+                }
+            }
+            self.watches[falsified.index()] = kept;
+            if conflict.is_some() {
+                self.head = self.trail.len();
+                return conflict;
+            }
+        }
+        None
+    }
+
+    /// Derives the first-UIP clause of a conflict and the level to return to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let level = self.trail_lim.len();
+        let mut learnt = vec![Lit(0)];
+        let mut pending = 0;
+        let mut index = self.trail.len();
+        let mut clause = conflict;
+        let mut uip = None;
+        loop {
+            let skip = if uip.is_some() { 1 } else { 0 };
+            for position in skip..self.clauses[clause].len() {
+                let lit = self.clauses[clause][position];
+                let var = lit.var();
+                if !self.seen[var] && self.levels[var] > 0 {
+                    self.seen[var] = true;
+                    self.bump(var);
+                    if self.levels[var] >= level {
+                        pending += 1;
+                    } else {
+                        learnt.push(lit);
+                    }
+                }
+            }
+            loop {
+                index -= 1;
+                if self.seen[self.trail[index].var()] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            self.seen[lit.var()] = false;
+            pending -= 1;
+            uip = Some(lit);
+            if pending == 0 {
+                break;
+            }
+            clause = self.reasons[lit.var()].unwrap();
+        }
+        learnt[0] = !uip.unwrap();
+        let mut back_level = 0;
+        for position in 1..learnt.len() {
+            let var = learnt[position].var();
+            self.seen[var] = false;
+            if self.levels[var] > back_level {
+                back_level = self.levels[var];
+                learnt.swap(1, position);
+            }
+        }
+        (learnt, back_level)
+    }
+
+    fn learn(&mut self, learnt: Vec<Lit>) {
+        let asserting = learnt[0];
+        if learnt.len() == 1 {
+            self.assign(asserting, None);
+        } else {
+            let index = self.attach(learnt);
+            self.assign(asserting, Some(index));
+        }
+    }
+
+    fn cancel_until(&mut self, level: usize) {
+        if self.trail_lim.len() <= level {
+            return;
+        }
+        let start = self.trail_lim[level];
+        for position in (start..self.trail.len()).rev() {
+            let lit = self.trail[position];
+            let var = lit.var();
+            self.values[var] = None;
+            self.reasons[var] = None;
+            self.phases[var] = !lit.is_negated();
+            if self.positions[var].is_none() {
+                self.heap_insert(var);
+            }
+        }
+        self.trail.truncate(start);
+        self.trail_lim.truncate(level);
+        self.head = start;
+    }
+
+    fn decide(&mut self) -> Option<Lit> {
+        while let Some(var) = self.heap_pop() {
+            if self.values[var].is_none() {
+                return Some(Lit::new(var, !self.phases[var]));
+            }
+        }
+        None
+    }
+
+    fn bump(&mut self, var: usize) {
+        if self.increment == 0.0 {
+            self.increment = 1.0;
+        }
+        self.activity[var] += self.increment;
+        if self.activity[var] > 1e100 {
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            self.increment *= 1e-100;
+        }
+        if let Some(position) = self.positions[var] {
+            self.sift_up(position);
+        }
+    }
+
+    fn heap_insert(&mut self, var: usize) {
+        self.positions[var] = Some(self.heap.len());
+        self.heap.push(var);
+        self.sift_up(self.heap.len() - 1);
+    }
+
+    fn heap_pop(&mut self) -> Option<usize> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let top = self.heap.swap_remove(0);
+        self.positions[top] = None;
+        if !self.heap.is_empty() {
+            self.positions[self.heap[0]] = Some(0);
+            self.sift_down(0);
+        }
+        Some(top)
+    }
+
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if self.activity[self.heap[parent]] >= self.activity[self.heap[position]] {
+                break;
+            }
+            self.heap_swap(parent, position);
+            position = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let mut largest = position;
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child < self.heap.len()
+                    && self.activity[self.heap[child]] > self.activity[self.heap[largest]]
+                {
+                    largest = child;
+                }
+            }
+            if largest == position {
+                break;
+            }
+            self.heap_swap(largest, position);
+            position = largest;
+        }
+    }
+
+    fn heap_swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a]] = Some(a);
+        self.positions[self.heap[b]] = Some(b);
+    }
+}
+
+fn luby(mut index: usize) -> usize {
+    let mut size = 1;
+    let mut exponent = 0;
+    while size < index + 1 {
+        exponent += 1;
+        size = 2 * size + 1;
+    }
+    while size - 1 != index {
+        size = (size - 1) / 2;
+        exponent -= 1;
+        index %= size;
+    }
+    1 << exponent
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{luby, Lit, Solver};
+
+    fn satisfies(solver: &Solver, clauses: &[Vec<Lit>]) -> bool {
+        clauses
+            .iter()
+            .all(|clause| clause.iter().any(|&lit| solver.model_value(lit)))
+    }
+
+    fn brute_force(vars: usize, clauses: &[Vec<Lit>]) -> bool {
+        (0..1u32 << vars).any(|assignment| {
+            clauses.iter().all(|clause| {
+                clause
+                    .iter()
+                    .any(|lit| (assignment >> lit.var() & 1 == 1) != lit.is_negated())
+            })
+        })
+    }
+
+    #[test]
+    fn luby_sequence() {
+        let sequence: Vec<usize> = (0..15).map(luby).collect();
+        assert_eq!(sequence, vec![1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn simple_satisfiable() {
+        let mut solver = Solver::default();
+        let a = solver.new_var();
+        let b = solver.new_var();
+        solver.add_clause(&[a, b]);
+        solver.add_clause(&[!a, b]);
+        solver.add_clause(&[a, !b]);
+        assert!(solver.solve());
+        assert!(solver.model_value(a) && solver.model_value(b));
+    }
+
+    #[test]
+    fn pigeonhole_unsatisfiable() {
+        // Four pigeons in three holes.
+        let mut solver = Solver::default();
+        let holes: Vec<Vec<Lit>> = (0..4)
+            .map(|_| (0..3).map(|_| solver.new_var()).collect())
+            .collect();
+        for pigeon in holes.iter() {
+            solver.add_clause(pigeon);
+        }
+        for (a, first) in holes.iter().enumerate() {
+            for second in holes[a + 1..].iter() {
+                for hole in 0..3 {
+                    solver.add_clause(&[!first[hole], !second[hole]]);
+                }
+            }
+        }
+        assert!(!solver.solve());
+    }
+
+    #[test]
+    fn assumptions_are_temporary() {
+        let mut solver = Solver::default();
+        let a = solver.new_var();
+        let b = solver.new_var();
+        solver.add_clause(&[!a, b]);
+        assert!(!solver.solve_with(&[a, !b]));
+        assert!(solver.solve_with(&[a]));
+        assert!(solver.model_value(b));
+        assert!(solver.solve());
+    }
+
+    #[test]
+    fn random_formulas_match_brute_force() {
+        let mut seed = 0x2545f491u64;
+        let mut random = move |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+        for _ in 0..300 {
+            let vars = 8;
+            let mut solver = Solver::default();
+            let lits: Vec<Lit> = (0..vars).map(|_| solver.new_var()).collect();
+            let clauses: Vec<Vec<Lit>> = (0..30 + random(15))
+                .map(|_| {
+                    (0..3)
+                        .map(|_| {
+                            let lit = lits[random(vars as u64) as usize];
+                            if random(2) == 0 {
+                                lit
+                            } else {
+                                !lit
+                            }
+                        })
+                        .collect()
+                })
+                .collect();
+            for clause in clauses.iter() {
+                solver.add_clause(clause);
+            }
+            let result = solver.solve();
+            assert_eq!(result, brute_force(vars, &clauses));
+            if result {
+                assert!(satisfies(&solver, &clauses));
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::network::Network;
+use module::Module;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+/// Replays every trace on its own network over the shared `module`, using
+/// up to `workers` threads, and returns the output trace of every input
+/// trace in order. Each worker keeps one network and resets it between
+/// traces, so no module is cloned.
+pub fn run_batch(
+    module: &Arc<Module>,
+    traces: &[Vec<Vec<bool>>],
+    workers: usize,
+) -> Vec<Vec<Vec<bool>>> {
+    let next_trace = AtomicUsize::new(0);
+    let results = Mutex::new(vec![vec![]; traces.len()]);
+    thread::scope(|scope| {
+        for _ in 0..workers.clamp(1, traces.len().max(1)) {
+            scope.spawn(|| {
+                let mut network = Network::new(module.clone());
+                loop {
+                    let index = next_trace.fetch_add(1, Ordering::Relaxed);
+                    if index >= traces.len() {
+                        break;
+                    }
+                    let outputs = network.replay(&traces[index]);
+                    results.lock().unwrap()[index] = outputs;
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{batch::run_batch, network::Network, random::Rng};
+    use module::ModuleBuilder;
+    use std::sync::Arc;
+
+    #[test]
+    fn batch_matches_replay() {
+        // A latch the input both charges and blocks, driving the output.
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 1);
+        builder.block(0, 1);
+        builder.charge(1, 2);
+        builder.input(0);
+        builder.output(2);
+        let module = Arc::new(builder.build());
+        let mut rng = Rng::new(11);
+        let traces: Vec<Vec<Vec<bool>>> = (0..100)
+            .map(|length| (0..length).map(|_| vec![rng.next_u64() & 1 == 1]).collect())
+            .collect();
+        let results = run_batch(&module, &traces, 4);
+        let mut network = Network::new(module.clone());
+        for (trace, outputs) in traces.iter().zip(results.iter()) {
+            assert_eq!(outputs.len(), trace.len());
+            assert_eq!(&network.replay(trace), outputs);
+        }
+    }
+
+    #[test]
+    fn empty_batch() {
+        let module = Arc::new(ModuleBuilder::default().build());
+        assert!(run_batch(&module, &[], 8).is_empty());
+    }
+}
@@ -0,0 +1,63 @@
+use crate::network::NodeState;
+
+/// Per-node counters collected by `Network::next` once coverage is enabled,
+/// from the states each tick starts and ends in, so the state left by the
+/// last tick counts too.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Coverage {
+    pub ticks: usize,
+    /// Ticks each node started or ended charged.
+    pub charged: Vec<usize>,
+    /// Ticks each node started or ended blocked.
+    pub blocked: Vec<usize>,
+    /// Ticks each node started charged and unblocked. A firing node fires all of
+    /// its charging and blocking edges at once, so this also counts how often
+    /// each of its edges fired.
+    pub fired: Vec<usize>,
+}
+
+impl Coverage {
+    pub fn new(size: usize) -> Coverage {
+        Coverage {
+            ticks: 0,
+            charged: vec![0; size],
+            blocked: vec![0; size],
+            fired: vec![0; size],
+        }
+    }
+
+    pub(crate) fn record(&mut self, before: &[NodeState], after: &[NodeState]) {
+        for (index, (before, after)) in before.iter().zip(after.iter()).enumerate() {
+            self.charged[index] += (before.is_charged() || after.is_charged()) as usize;
+            self.blocked[index] += (before.is_blocked() || after.is_blocked()) as usize;
+            self.fired[index] += (before.is_charged() && !before.is_blocked()) as usize;
+        }
+        self.ticks += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{coverage::Coverage, network::Network};
+    use module::ModuleBuilder;
+
+    #[test]
+    fn counts_ticks() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        let mut network = Network::new(builder.build());
+        network.enable_coverage();
+        network.charge(0);
+        network.next();
+        network.next();
+        assert_eq!(
+            network.coverage(),
+            Some(&Coverage {
+                ticks: 2,
+                charged: vec![1, 2],
+                blocked: vec![0, 0],
+                fired: vec![1, 1],
+            })
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use crate::network::{Network, NodeState};
+use std::collections::VecDeque;
+
+/// A `Network` that remembers the state deltas of its last ticks, so it can
+/// be stepped backward and replayed forward with different inputs.
+pub struct History {
+    network: Network,
+    deltas: VecDeque<Delta>,
+    pending: Delta,
+    capacity: usize,
+    tick: usize,
+}
+
+#[derive(Default)]
+struct Delta(Vec<(usize, NodeState)>);
+
+impl History {
+    pub fn new(network: Network, capacity: usize) -> History {
+        History {
+            network,
+            deltas: VecDeque::new(),
+            pending: Delta::default(),
+            capacity,
+            tick: 0,
+        }
+    }
+
+    pub fn charge(&mut self, index: usize) {
+        let state = self.network.state(index);
+        if !self.network.seek(index) {
+            self.pending.0.push((index, state));
+        }
+        self.network.charge(index);
+    }
+
+    /// Charges or discharges the input at `position` among the module inputs.
+    pub fn set_input(&mut self, position: usize, value: bool) {
+        let index = self.network.module().inputs[position];
+        if self.network.seek(index) != value {
+            self.pending.0.push((index, self.network.state(index)));
+        }
+        self.network.set_input(position, value);
+    }
+
+    /// Sets every input from `input_data`, by position.
+    pub fn set_inputs(&mut self, input_data: &[bool]) {
+        for (position, &value) in input_data.iter().enumerate() {
+            self.set_input(position, value);
+        }
+    }
+
+    pub fn seek(&self, index: usize) -> bool {
+        self.network.seek(index)
+    }
+
+    pub fn next(&mut self) {
+        let mut delta = std::mem::take(&mut self.pending);
+        self.network.step(Some(&mut delta.0));
+        if self.capacity > 0 {
+            if self.deltas.len() == self.capacity {
+                self.deltas.pop_front();
+            }
+            self.deltas.push_back(delta);
+        }
+        self.tick += 1;
+    }
+
+    /// Undoes up to `ticks` ticks (and any input charged since the last one),
+    /// returning how many ticks were actually undone.
+    pub fn back(&mut self, ticks: usize) -> usize {
+        let pending = std::mem::take(&mut self.pending);
+        self.rollback(pending);
+        let mut count = 0;
+        while count < ticks {
+            match self.deltas.pop_back() {
+                Some(delta) => self.rollback(delta),
+                None => break,
+            }
+            count += 1;
+        }
+        self.tick -= count;
+        count
+    }
+
+    /// The number of ticks elapsed since the network was created.
+    pub fn tick(&self) -> usize {
+        self.tick
+    }
+
+    /// The number of ticks that can currently be undone.
+    pub fn depth(&self) -> usize {
+        self.deltas.len()
+    }
+
+    pub fn network(&self) -> &Network {
+        &self.network
+    }
+
+    fn rollback(&mut self, delta: Delta) {
+        for (index, state) in delta.0.into_iter().rev() {
+            self.network.restore(index, state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{history::History, network::Network};
+    use module::ModuleBuilder;
+
+    fn chain() -> History {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.block(3, 1);
+        History::new(Network::new(builder.build()), 8)
+    }
+
+    #[test]
+    fn back_restores_previous_tick() {
+        let mut history = chain();
+        history.charge(0);
+        history.next();
+        history.next();
+        assert!(history.seek(2));
+
+        assert_eq!(history.back(1), 1);
+        assert!(history.seek(1) && !history.seek(2));
+        assert_eq!(history.back(1), 1);
+        assert!(!history.seek(0) && !history.seek(1));
+        assert_eq!(history.tick(), 0);
+    }
+
+    #[test]
+    fn replay_with_different_inputs() {
+        let mut history = chain();
+        history.charge(0);
+        history.next();
+        history.next();
+        history.back(2);
+
+        history.charge(0);
+        history.charge(3);
+        history.next();
+        history.next();
+        assert!(!history.seek(2));
+        assert_eq!(history.tick(), 2);
+    }
+
+    #[test]
+    fn back_discards_pending_inputs() {
+        let mut history = chain();
+        history.charge(0);
+        assert_eq!(history.back(1), 0);
+        assert!(!history.seek(0));
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut history = chain();
+        for _ in 0..20 {
+            history.next();
+        }
+        assert_eq!(history.depth(), 8);
+        assert_eq!(history.back(100), 8);
+        assert_eq!(history.tick(), 12);
+    }
+}
@@ -0,0 +1,7 @@
+pub mod batch;
+pub mod coverage;
+pub mod history;
+pub mod network;
+pub mod parallel;
+pub mod random;
+pub mod settle;
@@ -0,0 +1,355 @@
+use crate::coverage::Coverage;
+use module::Module;
+use std::sync::Arc;
+
+pub struct Network {
+    module: Arc<Module>,
+    states: Vec<NodeState>,
+    coverage: Option<Coverage>,
+}
+
+/// The state of one node, as saved by `Network::snapshot`.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct NodeState(u8);
+
+/// A snapshot with another number of nodes than the network loading it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct WrongSize {
+    pub expected: usize,
+    pub found: usize,
+}
+
+impl Network {
+    /// Creates a network over `module`, which can be shared with other
+    /// networks by passing an `Arc<Module>`.
+    pub fn new<M: Into<Arc<Module>>>(module: M) -> Network {
+        let module = module.into();
+        let mut states = vec![];
+        for _ in 0..module.connections.len() {
+            states.push(NodeState::default());
+        }
+        Network {
+            module,
+            states,
+            coverage: None,
+        }
+    }
+
+    pub fn charge(&mut self, index: usize) {
+        self.states[index].set_charged(true);
+    }
+
+    pub fn seek(&self, index: usize) -> bool {
+        self.states[index].is_charged()
+    }
+
+    /// Discharges and unblocks every node.
+    pub fn reset(&mut self) {
+        for state in self.states.iter_mut() {
+            *state = NodeState::default();
+        }
+    }
+
+    pub fn is_blocked(&self, index: usize) -> bool {
+        self.states[index].is_blocked()
+    }
+
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    /// Charges or discharges the input at `position` among the module inputs.
+    pub fn set_input(&mut self, position: usize, value: bool) {
+        let index = self.module.inputs[position];
+        self.states[index].set_charged(value);
+    }
+
+    /// Sets every input from `input_data`, by position.
+    pub fn set_inputs(&mut self, input_data: &[bool]) {
+        for (position, &value) in input_data.iter().enumerate() {
+            self.set_input(position, value);
+        }
+    }
+
+    /// Whether the output at `position` among the module outputs is charged.
+    pub fn output(&self, position: usize) -> bool {
+        self.seek(self.module.outputs[position])
+    }
+
+    pub fn outputs(&self) -> Vec<bool> {
+        self.module
+            .outputs
+            .iter()
+            .map(|&index| self.seek(index))
+            .collect()
+    }
+
+    pub fn next(&mut self) {
+        self.step(None);
+    }
+
+    /// Replays `trace` from reset, charging the inputs set on every line
+    /// before ticking, and returns the outputs after every tick.
+    pub fn replay(&mut self, trace: &[Vec<bool>]) -> Vec<Vec<bool>> {
+        self.reset();
+        let mut outputs = vec![];
+        for input_data in trace {
+            self.apply(input_data);
+            outputs.push(self.outputs());
+        }
+        outputs
+    }
+
+    /// Starts counting, on every following tick, which nodes are charged,
+    /// blocked and firing.
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(Coverage::new(self.states.len()));
+    }
+
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Charges the module inputs set in `input_data`, by position, leaving
+    /// the others as they are.
+    pub fn charge_inputs(&mut self, input_data: &[bool]) {
+        for (position, &bit) in input_data.iter().enumerate() {
+            if bit {
+                self.charge(self.module.inputs[position]);
+            }
+        }
+    }
+
+    /// Charges the module inputs set in `input_data`, by position, then
+    /// ticks.
+    pub fn apply(&mut self, input_data: &[bool]) {
+        self.charge_inputs(input_data);
+        self.next();
+    }
+
+    pub(crate) fn state(&self, index: usize) -> NodeState {
+        self.states[index]
+    }
+
+    /// The state of every node, which `load` can restore later.
+    pub fn snapshot(&self) -> Vec<NodeState> {
+        self.states.clone()
+    }
+
+    /// Charges or discharges any node, e.g. to inject a fault.
+    pub fn force(&mut self, index: usize, charged: bool) {
+        self.states[index].set_charged(charged);
+    }
+
+    /// Restores a snapshot. It must come from a network of the same size.
+    pub fn load(&mut self, states: &[NodeState]) -> Result<(), WrongSize> {
+        if states.len() != self.states.len() {
+            return Err(WrongSize {
+                expected: self.states.len(),
+                found: states.len(),
+            });
+        }
+        self.states.copy_from_slice(states);
+        Ok(())
+    }
+
+    pub(crate) fn restore(&mut self, index: usize, state: NodeState) {
+        self.states[index] = state;
+    }
+
+    /// Advances one tick, pushing the previous state of every node whose
+    /// state changed into `changes` when one is given.
+    pub(crate) fn step(&mut self, mut changes: Option<&mut Vec<(usize, NodeState)>>) {
+        let before = self.coverage.as_ref().map(|_| self.states.clone());
+        for index in 0..self.states.len() {
+            if self.states[index].is_charged() && !self.states[index].is_blocked() {
+                for other_index in self.module.connections[index].charging.iter() {
+                    self.states[*other_index].set_being_charged(true);
+                }
+                for other_index in self.module.connections[index].blocking.iter() {
+                    self.states[*other_index].set_being_blocked(true);
+                }
+            }
+        }
+        for index in 0..self.module.connections.len() {
+            let previous = self.states[index].settled();
+            let charged = self.states[index].get_being_charged();
+            let blocked = self.states[index].get_being_blocked();
+            self.states[index].set_charged(charged);
+            self.states[index].set_being_charged(false);
+            self.states[index].set_blocked(blocked);
+            self.states[index].set_being_blocked(false);
+            if let Some(changes) = changes.as_mut() {
+                if self.states[index] != previous {
+                    changes.push((index, previous));
+                }
+            }
+        }
+        if let (Some(coverage), Some(before)) = (self.coverage.as_mut(), before) {
+            coverage.record(&before, &self.states);
+        }
+    }
+}
+
+impl NodeState {
+    fn settled(self) -> NodeState {
+        NodeState(self.0 & 0b0011)
+    }
+
+    fn set_charged(&mut self, value: bool) {
+        if value {
+            self.0 |= 0b0001;
+        } else {
+            self.0 &= 0b1110;
+        }
+    }
+
+    fn set_blocked(&mut self, value: bool) {
+        if value {
+            self.0 |= 0b0010;
+        } else {
+            self.0 &= 0b1101;
+        }
+    }
+
+    fn set_being_charged(&mut self, value: bool) {
+        if value {
+            self.0 |= 0b0100;
+        } else {
+            self.0 &= 0b1011;
+        }
+    }
+
+    fn set_being_blocked(&mut self, value: bool) {
+        if value {
+            self.0 |= 0b1000;
+        } else {
+            self.0 &= 0b0111;
+        }
+    }
+
+    pub fn is_charged(&self) -> bool {
+        self.0 % 2 == 0b0001
+    }
+
+    pub fn is_blocked(&self) -> bool {
+        self.0 % 4 >= 0b0010
+    }
+
+    fn get_being_charged(&self) -> bool {
+        self.0 % 8 >= 0b0100
+    }
+
+    fn get_being_blocked(&self) -> bool {
+        self.0 % 16 >= 0b1000
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::network::{Network, WrongSize};
+    use module::ModuleBuilder;
+
+    #[test]
+    fn input_charging() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+
+        let mut network = Network::new(builder.build());
+        network.charge(0);
+        network.next();
+        let charged = network.seek(1);
+
+        assert!(charged);
+    }
+
+    #[test]
+    fn input_charging_discharges_input() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+
+        let mut network = Network::new(builder.build());
+        network.charge(0);
+        network.next();
+        let charged = network.seek(0);
+
+        assert!(!charged);
+    }
+
+    #[test]
+    fn input_charging_chain() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(2, 3);
+        builder.charge(3, 4);
+
+        let mut network = Network::new(builder.build());
+        network.charge(0);
+        network.next();
+        network.next();
+        network.next();
+        let charged = network.seek(3);
+        let discharged =
+            !network.seek(0) && !network.seek(1) && !network.seek(2) && !network.seek(4);
+
+        assert!(charged && discharged);
+    }
+
+    #[test]
+    fn basic_blocking() {
+        let mut builder = ModuleBuilder::default();
+        builder.block(0, 2);
+        builder.charge(1, 2);
+        builder.charge(2, 3);
+
+        let mut network = Network::new(builder.build());
+        network.charge(0);
+        network.charge(1);
+        network.next();
+        network.next();
+        let charged = network.seek(3);
+        assert!(!charged);
+    }
+
+    #[test]
+    fn named_ports() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 2);
+        builder.block(1, 2);
+        builder.charge(2, 3);
+        builder.input(0);
+        builder.input(1);
+        builder.output(3);
+
+        let mut network = Network::new(builder.build());
+        network.set_inputs(&[true, true]);
+        network.set_input(1, false);
+        network.next();
+        network.next();
+        assert_eq!(network.outputs(), vec![true]);
+        assert!(network.output(0));
+    }
+
+    #[test]
+    fn load_checks_size() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.input(0);
+        builder.output(1);
+
+        let mut network = Network::new(builder.build());
+        let reset = network.snapshot();
+        network.apply(&[true]);
+        assert_eq!(network.outputs(), vec![true]);
+        network.load(&reset).unwrap();
+        assert_eq!(network.outputs(), vec![false]);
+        assert_eq!(
+            network.load(&reset[1..]),
+            Err(WrongSize {
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use module::{Module, NodeConnections};
+use std::{
+    cmp::min,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Barrier,
+    },
+    thread::{self, JoinHandle},
+};
+
+/// A network that splits every tick across a pool of worker threads,
+/// started once in `new` and stopped on drop. Node states are packed into
+/// bitsets; each worker propagates the active nodes of its share of words
+/// into its own `being_charged`/`being_blocked` bitsets, then, after a
+/// barrier, commits its share of words by merging those bitsets. The
+/// results are identical to `Network` whatever the thread count.
+pub struct ParallelNetwork {
+    module: Arc<Module>,
+    shared: Arc<Shared>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+/// State the workers and the owning thread share. The barriers order every
+/// access, so the atomics only need relaxed loads and stores.
+struct Shared {
+    charging: Adjacency,
+    blocking: Adjacency,
+    charged: Vec<AtomicU64>,
+    blocked: Vec<AtomicU64>,
+    /// `being_charged` and `being_blocked` of every worker.
+    scratch: Vec<(Vec<AtomicU64>, Vec<AtomicU64>)>,
+    /// Words per worker.
+    chunk: usize,
+    /// Waited on by the workers and the owning thread to start a tick.
+    start: Barrier,
+    /// Waited on by the workers between propagating and committing.
+    propagated: Barrier,
+    /// Waited on by the workers and the owning thread to end a tick.
+    done: Barrier,
+    stop: AtomicBool,
+}
+
+/// Sorted targets of every node, stored contiguously.
+struct Adjacency {
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+}
+
+impl ParallelNetwork {
+    pub fn new(module: Arc<Module>, threads: usize) -> ParallelNetwork {
+        let threads = threads.max(1);
+        let words = module.connections.len().div_ceil(64);
+        let bitset = || (0..words).map(|_| AtomicU64::new(0)).collect::<Vec<_>>();
+        let shared = Arc::new(Shared {
+            charging: Adjacency::new(&module, |con| con.charging.iter()),
+            blocking: Adjacency::new(&module, |con| con.blocking.iter()),
+            charged: bitset(),
+            blocked: bitset(),
+            scratch: (0..threads).map(|_| (bitset(), bitset())).collect(),
+            chunk: words.div_ceil(threads),
+            start: Barrier::new(threads + 1),
+            propagated: Barrier::new(threads),
+            done: Barrier::new(threads + 1),
+            stop: AtomicBool::new(false),
+        });
+        let workers = (0..threads)
+            .map(|worker| {
+                let shared = shared.clone();
+                thread::spawn(move || shared.work(worker))
+            })
+            .collect();
+        ParallelNetwork {
+            module,
+            shared,
+            workers,
+        }
+    }
+
+    pub fn charge(&mut self, index: usize) {
+        self.shared.charged[index / 64].fetch_or(1 << (index % 64), Ordering::Relaxed);
+    }
+
+    pub fn seek(&self, index: usize) -> bool {
+        self.shared.charged[index / 64].load(Ordering::Relaxed) >> (index % 64) & 1 == 1
+    }
+
+    pub fn is_blocked(&self, index: usize) -> bool {
+        self.shared.blocked[index / 64].load(Ordering::Relaxed) >> (index % 64) & 1 == 1
+    }
+
+    /// Discharges and unblocks every node.
+    pub fn reset(&mut self) {
+        for word in self.shared.charged.iter().chain(self.shared.blocked.iter()) {
+            word.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn module(&self) -> &Module {
+        &self.module
+    }
+
+    pub fn threads(&self) -> usize {
+        self.workers.len()
+    }
+
+    pub fn next(&mut self) {
+        self.shared.start.wait();
+        self.shared.done.wait();
+    }
+}
+
+impl Drop for ParallelNetwork {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        self.shared.start.wait();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Shared {
+    /// The loop of worker `index`, one tick per round until stopped.
+    fn work(&self, index: usize) {
+        let words = self.charged.len();
+        let start = min(index * self.chunk, words);
+        let end = min(start + self.chunk, words);
+        let (being_charged, being_blocked) = &self.scratch[index];
+        loop {
+            self.start.wait();
+            if self.stop.load(Ordering::Relaxed) {
+                return;
+            }
+            for word in start..end {
+                let mut active = self.charged[word].load(Ordering::Relaxed)
+                    & !self.blocked[word].load(Ordering::Relaxed);
+                while active != 0 {
+                    let node = word * 64 + active.trailing_zeros() as usize;
+                    active &= active - 1;
+                    for &to in self.charging.targets(node) {
+                        being_charged[to / 64].fetch_or(1 << (to % 64), Ordering::Relaxed);
+                    }
+                    for &to in self.blocking.targets(node) {
+                        being_blocked[to / 64].fetch_or(1 << (to % 64), Ordering::Relaxed);
+                    }
+                }
+            }
+            self.propagated.wait();
+            for word in start..end {
+                let mut charged = 0;
+                let mut blocked = 0;
+                for (being_charged, being_blocked) in self.scratch.iter() {
+                    charged |= being_charged[word].swap(0, Ordering::Relaxed);
+                    blocked |= being_blocked[word].swap(0, Ordering::Relaxed);
+                }
+                self.charged[word].store(charged, Ordering::Relaxed);
+                self.blocked[word].store(blocked, Ordering::Relaxed);
+            }
+            self.done.wait();
+        }
+    }
+}
+
+impl Adjacency {
+    fn new<'a, F, I>(module: &'a Module, targets_of: F) -> Adjacency
+    where
+        F: Fn(&'a NodeConnections) -> I,
+        I: Iterator<Item = &'a usize>,
+    {
+        let mut offsets = vec![0];
+        let mut targets = vec![];
+        for con in module.connections.iter() {
+            let start = targets.len();
+            targets.extend(targets_of(con).copied());
+            targets[start..].sort_unstable();
+            offsets.push(targets.len());
+        }
+        Adjacency { offsets, targets }
+    }
+
+    fn targets(&self, node: usize) -> &[usize] {
+        &self.targets[self.offsets[node]..self.offsets[node + 1]]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{network::Network, parallel::ParallelNetwork, random::Rng};
+    use module::ModuleBuilder;
+    use std::sync::Arc;
+
+    #[test]
+    fn single_word() {
+        let mut builder = ModuleBuilder::default();
+        builder.block(0, 2);
+        builder.charge(1, 2);
+        builder.charge(2, 3);
+
+        let mut network = ParallelNetwork::new(Arc::new(builder.build()), 2);
+        network.charge(0);
+        network.charge(1);
+        network.next();
+        assert!(network.seek(2) && network.is_blocked(2));
+        network.next();
+        assert!(!network.seek(3));
+    }
+
+    #[test]
+    fn matches_sequential_engine() {
+        let mut rng = Rng::new(7);
+        let size = 1000;
+        let mut builder = ModuleBuilder::default();
+        for from in 0..size {
+            for _ in 0..3 {
+                let to = rng.next_u64() as usize % size;
+                builder.connect(from, to, rng.next_u64() & 3 != 0);
+            }
+        }
+        for input in 0..8 {
+            builder.input(input * 100);
+        }
+        let module = Arc::new(builder.build());
+        for threads in [1, 3, 8, 40] {
+            let mut sequential = Network::new(module.clone());
+            let mut parallel = ParallelNetwork::new(module.clone(), threads);
+            let mut rng = Rng::new(threads as u64);
+            for _ in 0..200 {
+                for &input in module.inputs.iter() {
+                    if rng.next_u64() & 3 == 0 {
+                        sequential.charge(input);
+                        parallel.charge(input);
+                    }
+                }
+                sequential.next();
+                parallel.next();
+                for index in 0..size {
+                    assert_eq!(sequential.seek(index), parallel.seek(index));
+                    assert_eq!(sequential.is_blocked(index), parallel.is_blocked(index));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/// A xorshift generator, so that a seed always produces the same modules and
+/// traces.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::random::Rng;
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let first: Vec<u64> = (0..5)
+            .scan(Rng::new(3), |rng, _| Some(rng.next_u64()))
+            .collect();
+        let second: Vec<u64> = (0..5)
+            .scan(Rng::new(3), |rng, _| Some(rng.next_u64()))
+            .collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn zero_seed_is_not_stuck() {
+        assert_ne!(Rng::new(0).next_u64(), 0);
+    }
+}
@@ -0,0 +1,97 @@
+use crate::network::Network;
+use std::collections::HashMap;
+
+/// How a network behaves under fixed inputs once its state starts repeating.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Settling {
+    /// Ticks elapsed before the first state of the cycle was reached.
+    pub transient: usize,
+    /// Length of the cycle; `1` means the network is stable.
+    pub period: usize,
+}
+
+impl Settling {
+    pub fn is_stable(&self) -> bool {
+        self.period == 1
+    }
+}
+
+/// Sets the inputs from `input_data` before every tick until the full network
+/// state repeats, giving up after `max_ticks` ticks. The network is left in
+/// the first repeated state.
+pub fn settle(network: &mut Network, input_data: &[bool], max_ticks: usize) -> Option<Settling> {
+    let mut seen = HashMap::new();
+    seen.insert(network.snapshot(), 0);
+    for tick in 1..=max_ticks {
+        network.set_inputs(input_data);
+        network.next();
+        if let Some(&first) = seen.get(&network.snapshot()) {
+            return Some(Settling {
+                transient: first,
+                period: tick - first,
+            });
+        }
+        seen.insert(network.snapshot(), tick);
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        network::Network,
+        settle::{settle, Settling},
+    };
+    use module::ModuleBuilder;
+
+    #[test]
+    fn chain_settles() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(2, 3);
+        builder.input(0);
+
+        let mut network = Network::new(builder.build());
+        let settling = settle(&mut network, &[true], 100).unwrap();
+        assert_eq!(
+            settling,
+            Settling {
+                transient: 3,
+                period: 1
+            }
+        );
+        assert!(network.seek(3));
+    }
+
+    #[test]
+    fn ring_oscillates() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(2, 0);
+
+        let mut network = Network::new(builder.build());
+        network.charge(0);
+        let settling = settle(&mut network, &[], 100).unwrap();
+        assert_eq!(
+            settling,
+            Settling {
+                transient: 0,
+                period: 3
+            }
+        );
+    }
+
+    #[test]
+    fn gives_up_at_tick_cap() {
+        let mut builder = ModuleBuilder::default();
+        builder.charge(0, 1);
+        builder.charge(1, 2);
+        builder.charge(2, 3);
+        builder.input(0);
+
+        let mut network = Network::new(builder.build());
+        assert_eq!(settle(&mut network, &[true], 2), None);
+    }
+}